@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use superstack_rust_sdk::SuperstackApiClient;
+
+#[tokio::main]
+async fn main() {
+    // Load environment variables from .env file
+    dotenv::dotenv().ok();
+
+    // Read configuration from environment variables
+    let api_key = std::env::var("API_KEY").unwrap();
+
+    // Create a new superstack API client
+    let client = SuperstackApiClient::new(&api_key);
+
+    // Query the mid prices of all coins once
+    let mids = client.all_mids(None).await.unwrap();
+    println!("BTC mid: {}", mids["BTC"]);
+
+    // Keep the mid prices refreshed in the background
+    let client = client.with_mids_cache(None, Duration::from_secs(1));
+    for _ in 0..5 {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        println!("Cached BTC mid: {:?}", client.cached_mid("BTC"));
+    }
+}
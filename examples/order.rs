@@ -25,6 +25,7 @@ async fn main() {
             }),
         }],
         grouping: "na".to_string(),
+        builder: None,
     };
 
     // Place the order
@@ -28,6 +28,7 @@ async fn main() {
             }),
         }],
         grouping: "na".to_string(),
+        builder: None,
     };
     let response = client.order(order, None, None).await.unwrap();
     println!("Order response: {:?}", response);
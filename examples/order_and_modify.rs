@@ -28,6 +28,7 @@ async fn main() {
             }),
         }],
         grouping: "na".to_string(),
+        builder: None,
     };
 
     // Place the order
@@ -0,0 +1,50 @@
+use superstack_rust_sdk::hype_info::HypeLedgerDelta;
+use superstack_rust_sdk::SuperstackApiClient;
+
+#[tokio::main]
+async fn main() {
+    // Load environment variables from .env file
+    dotenv::dotenv().ok();
+
+    // Read configuration from environment variables
+    let api_key = std::env::var("API_KEY").unwrap();
+
+    // Create a new superstack API client
+    let client = SuperstackApiClient::new(&api_key);
+
+    // Get the EVM main wallet address
+    let user = client.get_evm_main_wallet_address().await.unwrap();
+
+    // Query the last 7 days
+    let start_time = (chrono::Utc::now() - chrono::Duration::days(7)).timestamp_millis() as u64;
+
+    let fundings = client.user_funding(user, start_time, None).await.unwrap();
+    for funding in &fundings {
+        println!(
+            "Funding {} {} USDC (rate {})",
+            funding.delta.coin, funding.delta.usdc, funding.delta.funding_rate
+        );
+    }
+
+    let updates = client
+        .user_non_funding_ledger_updates(user, start_time, None)
+        .await
+        .unwrap();
+    for update in &updates {
+        match &update.delta {
+            HypeLedgerDelta::InternalTransfer {
+                usdc, destination, ..
+            } => println!("USDC transfer of {} to {}", usdc, destination),
+            HypeLedgerDelta::SpotTransfer {
+                token,
+                amount,
+                destination,
+                ..
+            } => println!("Spot transfer of {} {} to {}", amount, token, destination),
+            HypeLedgerDelta::AccountClassTransfer { usdc, to_perp } => {
+                println!("USD class transfer of {} (to perp: {})", usdc, to_perp)
+            }
+            other => println!("{:?}", other),
+        }
+    }
+}
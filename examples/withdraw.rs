@@ -0,0 +1,22 @@
+use superstack_rust_sdk::SuperstackApiClient;
+
+#[tokio::main]
+async fn main() {
+    // Load environment variables from .env file
+    dotenv::dotenv().ok();
+
+    // Read configuration from environment variables
+    let api_key = std::env::var("API_KEY").unwrap();
+
+    // Create a new superstack API client
+    let client = SuperstackApiClient::new(&api_key);
+
+    let destination = "0x0000000000000000000000000000000000000000";
+    // Withdraw 2 USDC to the destination address on Arbitrum
+    let result = client.withdraw("2", destination).await.unwrap();
+    // The fee is confirmed later by the withdraw ledger update, see `apply_ledger_delta`
+    println!(
+        "Withdraw response: {:?}, assumed fee: {}, nonce: {}",
+        result.response, result.assumed_fee, result.nonce
+    );
+}
@@ -0,0 +1,32 @@
+use futures_util::StreamExt;
+use superstack_rust_sdk::SuperstackApiClient;
+
+#[tokio::main]
+async fn main() {
+    // Load environment variables from .env file
+    dotenv::dotenv().ok();
+
+    // Read configuration from environment variables
+    let api_key = std::env::var("API_KEY").unwrap();
+
+    // Create a new superstack API client and connect to the hyperliquid websocket
+    let client = SuperstackApiClient::new(&api_key);
+    let ws = client.connect_ws().await.unwrap();
+
+    // Stream the BTC book and trades
+    let mut book = ws.l2_book("BTC").await.unwrap();
+    let mut trades = ws.trades("BTC").await.unwrap();
+
+    for _ in 0..10 {
+        tokio::select! {
+            Some(book) = book.next() => {
+                println!("Best bid: {:?}, best ask: {:?}", book.levels[0].first(), book.levels[1].first());
+            }
+            Some(trades) = trades.next() => {
+                for trade in trades {
+                    println!("Trade: {} {} @ {}", trade.side, trade.sz, trade.px);
+                }
+            }
+        }
+    }
+}
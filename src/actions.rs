@@ -14,6 +14,23 @@ pub enum Actions {
     UsdSend(UsdSend),
     SpotSend(SpotSend),
     UsdClassTransfer(UsdClassTransfer),
+    Withdraw3(Withdraw3),
+    ApproveAgent(ApproveAgent),
+    ApproveBuilderFee(ApproveBuilderFee),
+    VaultTransfer(VaultTransfer),
+    CreateSubAccount(CreateSubAccount),
+    SubAccountModify(SubAccountModify),
+    SubAccountTransfer(SubAccountTransfer),
+    SubAccountSpotTransfer(SubAccountSpotTransfer),
+    TwapOrder(TwapOrder),
+    TwapCancel(TwapCancel),
+    TopUpIsolatedOnlyMargin(TopUpIsolatedOnlyMargin),
+    CDeposit(CDeposit),
+    CWithdraw(CWithdraw),
+    TokenDelegate(TokenDelegate),
+    SendAsset(SendAsset),
+    // Does nothing but mark its nonce as used
+    Noop,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -21,6 +38,17 @@ pub enum Actions {
 pub struct BulkOrder {
     pub orders: Vec<OrderRequest>,
     pub grouping: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub builder: Option<BuilderInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BuilderInfo {
+    #[serde(rename = "b", alias = "builder")]
+    pub builder: String,
+    // Tenths of a basis point, e.g. 10 is 0.01%
+    #[serde(rename = "f", alias = "fee")]
+    pub fee: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -64,6 +92,14 @@ pub struct UpdateIsolatedMargin {
     pub ntli: i64,
 }
 
+// Adds margin to an isolated position until it is at `leverage`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TopUpIsolatedOnlyMargin {
+    pub asset: u32,
+    pub leverage: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct UsdSend {
@@ -79,6 +115,19 @@ pub struct SpotSend {
     pub amount: String,
 }
 
+// Dexes are "" for the default perp dex, "spot" for spot or the name of a builder-deployed dex
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SendAsset {
+    pub destination: String,
+    pub source_dex: String,
+    pub destination_dex: String,
+    pub token: String,
+    pub amount: String,
+    // Empty when sending from the main account
+    pub from_sub_account: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct UsdClassTransfer {
@@ -86,6 +135,122 @@ pub struct UsdClassTransfer {
     pub to_perp: bool,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Withdraw3 {
+    pub destination: String,
+    pub amount: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApproveAgent {
+    pub agent_address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApproveBuilderFee {
+    // Percentage, e.g. "0.01%"
+    pub max_fee_rate: String,
+    pub builder: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultTransfer {
+    pub vault_address: String,
+    pub is_deposit: bool,
+    // Micro USDC, 1 USDC is 1_000_000
+    pub usd: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSubAccount {
+    pub name: String,
+}
+
+// Renames a sub-account
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SubAccountModify {
+    pub sub_account_user: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SubAccountTransfer {
+    pub sub_account_user: String,
+    pub is_deposit: bool,
+    // Micro USDC, 1 USDC is 1_000_000
+    pub usd: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SubAccountSpotTransfer {
+    pub sub_account_user: String,
+    pub is_deposit: bool,
+    pub token: String,
+    pub amount: String,
+}
+
+// Moves HYPE from the spot balance to the staking balance
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CDeposit {
+    // HYPE in units of 1e-8
+    pub wei: u64,
+}
+
+// Moves HYPE from the staking balance back to the spot balance
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CWithdraw {
+    // HYPE in units of 1e-8
+    pub wei: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenDelegate {
+    pub validator: String,
+    pub is_undelegate: bool,
+    // HYPE in units of 1e-8
+    pub wei: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TwapOrder {
+    pub twap: TwapRequest,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TwapRequest {
+    #[serde(rename = "a", alias = "asset")]
+    pub asset: u32,
+    #[serde(rename = "b", alias = "isBuy")]
+    pub is_buy: bool,
+    #[serde(rename = "s", alias = "sz")]
+    pub sz: String,
+    #[serde(rename = "r", alias = "reduceOnly", default)]
+    pub reduce_only: bool,
+    #[serde(rename = "m", alias = "minutes")]
+    pub minutes: u32,
+    #[serde(rename = "t", alias = "randomize", default)]
+    pub randomize: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TwapCancel {
+    #[serde(rename = "a", alias = "asset")]
+    pub asset: u32,
+    #[serde(rename = "t", alias = "twapId")]
+    pub twap_id: u64,
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Limit {
     pub tif: String,
@@ -144,3 +309,59 @@ pub struct ModifyRequest {
     pub oid: u64,
     pub order: OrderRequest,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_with_builder_wire_order() {
+        let action = Actions::Order(BulkOrder {
+            orders: vec![OrderRequest {
+                asset: 0,
+                is_buy: true,
+                limit_px: "100000".to_string(),
+                sz: "0.0001".to_string(),
+                reduce_only: false,
+                order_type: Order::Limit(Limit {
+                    tif: "Gtc".to_string(),
+                }),
+                cloid: None,
+            }],
+            grouping: "na".to_string(),
+            builder: Some(BuilderInfo {
+                builder: "0x0000000000000000000000000000000000000abc".to_string(),
+                fee: 10,
+            }),
+        });
+        // The action is hashed in field order, the builder goes after the grouping
+        assert_eq!(
+            serde_json::to_string(&action).unwrap(),
+            r#"{"type":"order","orders":[{"a":0,"b":true,"p":"100000","s":"0.0001","r":false,"t":{"limit":{"tif":"Gtc"}}}],"grouping":"na","builder":{"b":"0x0000000000000000000000000000000000000abc","f":10}}"#
+        );
+    }
+
+    #[test]
+    fn test_send_asset_serialization() {
+        let action = Actions::SendAsset(SendAsset {
+            destination: "0x0000000000000000000000000000000000000abc".to_string(),
+            source_dex: "".to_string(),
+            destination_dex: "spot".to_string(),
+            token: "USDC:0x6d1e7cde53ba9467b783cb7c530ce054".to_string(),
+            amount: "1".to_string(),
+            from_sub_account: "".to_string(),
+        });
+        assert_eq!(
+            serde_json::to_string(&action).unwrap(),
+            r#"{"type":"sendAsset","destination":"0x0000000000000000000000000000000000000abc","sourceDex":"","destinationDex":"spot","token":"USDC:0x6d1e7cde53ba9467b783cb7c530ce054","amount":"1","fromSubAccount":""}"#
+        );
+    }
+
+    #[test]
+    fn test_noop_serialization() {
+        assert_eq!(
+            serde_json::to_string(&Actions::Noop).unwrap(),
+            r#"{"type":"noop"}"#
+        );
+    }
+}
@@ -1,27 +1,58 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
 use alloy::primitives::Address;
 use anyhow::Result;
 use reqwest::Client;
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
 
 use crate::actions::*;
-use crate::hype_responses::{HypeExchangeResponse, HypeExchangeResponseStatus};
+use crate::hype_info::{
+    HypeActiveAssetData, HypeClearinghouseState, HypeDelegation, HypeDelegatorReward,
+    HypeDelegatorSummary, HypeExtraAgent, HypeHistoricalOrder, HypeLedgerUpdate, HypeMeta,
+    HypePortfolio, HypePosition, HypeSubAccount, HypeTwapSliceFill, HypeUserFees, HypeUserFunding,
+    HypeUserRateLimit, HypeUserVaultEquity, HypeValidatorSummary, HypeVaultDetails, InfoRequest,
+};
+use crate::hype_responses::{
+    HypeExchangeDataStatus, HypeExchangeError, HypeExchangeResponse, HypeExchangeResponseStatus,
+    HypeIsolatedMarginResult, HypeVaultTransferError, HypeVaultTransferResult, HypeWithdrawResult,
+    WITHDRAW3_ASSUMED_FEE_USDC,
+};
+use crate::mids_cache::MidsCache;
 use crate::types::{
     ApiInfoResponse, ErrorResponse, ExchangePayload, ExchangeRequest, ExchangeResponse,
 };
+use crate::ws_client::{ws_url, WsClient, WsConfig, WsStream};
+use crate::ws_messages::{
+    WsOrderUpdate, WsUserEvent, WsUserFills, WsUserFundingsData, WsUserNonFundingLedgerUpdatesData,
+    WsWebData2,
+};
 
+#[derive(Clone)]
 pub struct SuperstackApiClient {
     http_client: Client,
     base_url: String,
+    hyperliquid_url: String,
     api_key: String,
+    mids_cache: Option<MidsCache>,
+    exchange_ws: Option<WsClient>,
+    builder: Option<BuilderInfo>,
 }
 
 impl SuperstackApiClient {
     const BASE_URL: &str = "https://wallet-service.superstack.xyz";
+    const HYPERLIQUID_URL: &str = "https://api.hyperliquid.xyz";
 
     pub fn new(api_key: &str) -> Self {
         Self {
             http_client: Client::new(),
             base_url: Self::BASE_URL.to_string(),
+            hyperliquid_url: Self::HYPERLIQUID_URL.to_string(),
             api_key: api_key.to_string(),
+            mids_cache: None,
+            exchange_ws: None,
+            builder: None,
         }
     }
 
@@ -29,7 +60,11 @@ impl SuperstackApiClient {
         Self {
             http_client: Client::new(),
             base_url: base_url.to_string(),
+            hyperliquid_url: Self::HYPERLIQUID_URL.to_string(),
             api_key: api_key.to_string(),
+            mids_cache: None,
+            exchange_ws: None,
+            builder: None,
         }
     }
 
@@ -41,14 +76,127 @@ impl SuperstackApiClient {
         Self {
             http_client,
             base_url: base_url.to_string(),
+            hyperliquid_url: Self::HYPERLIQUID_URL.to_string(),
             api_key: api_key.to_string(),
+            mids_cache: None,
+            exchange_ws: None,
+            builder: None,
+        }
+    }
+
+    pub fn with_hyperliquid_url(mut self, hyperliquid_url: &str) -> Self {
+        self.hyperliquid_url = hyperliquid_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    // Must be called from within a tokio runtime, the cache is refreshed by a spawned task.
+    pub fn with_mids_cache(mut self, dex: Option<&str>, refresh_interval: Duration) -> Self {
+        let mut fetcher = self.clone();
+        fetcher.mids_cache = None;
+        self.mids_cache = Some(MidsCache::spawn(
+            fetcher,
+            dex.map(|dex| dex.to_string()),
+            refresh_interval,
+        ));
+        self
+    }
+
+    pub fn mids_cache(&self) -> Option<&MidsCache> {
+        self.mids_cache.as_ref()
+    }
+
+    pub fn cached_mid(&self, coin: &str) -> Option<Decimal> {
+        self.mids_cache.as_ref().and_then(|cache| cache.get(coin))
+    }
+
+    // Connects to the websocket endpoint of the configured hyperliquid url
+    pub async fn connect_ws(&self) -> Result<WsClient> {
+        WsClient::connect(&ws_url(&self.hyperliquid_url)).await
+    }
+
+    // Attached to every order placed without a builder. `fee` is in tenths of a basis
+    // point and must not exceed the fee approved with `approve_builder_fee`.
+    pub fn with_builder(mut self, builder: Address, fee: u64) -> Self {
+        self.builder = Some(BuilderInfo {
+            builder: format!("{:#x}", builder),
+            fee,
+        });
+        self
+    }
+
+    // Signed actions are posted over this websocket instead of the /exchange endpoint
+    pub fn with_exchange_ws(mut self, ws: WsClient) -> Self {
+        self.exchange_ws = Some(ws);
+        self
+    }
+
+    pub async fn connect_ws_with_config(&self, config: WsConfig) -> Result<WsClient> {
+        WsClient::connect_with_config(&ws_url(&self.hyperliquid_url), config).await
+    }
+
+    // User streams of `user`, defaulting to the main wallet of the API key like `portfolio`
+    pub async fn ws_order_updates(
+        &self,
+        ws: &WsClient,
+        user: Option<Address>,
+    ) -> Result<WsStream<Vec<WsOrderUpdate>>> {
+        ws.order_updates(self.user_or_main_wallet(user).await?)
+            .await
+    }
+
+    pub async fn ws_user_fills(
+        &self,
+        ws: &WsClient,
+        user: Option<Address>,
+    ) -> Result<WsStream<WsUserFills>> {
+        ws.user_fills(self.user_or_main_wallet(user).await?).await
+    }
+
+    pub async fn ws_user_events(
+        &self,
+        ws: &WsClient,
+        user: Option<Address>,
+    ) -> Result<WsStream<WsUserEvent>> {
+        ws.user_events(self.user_or_main_wallet(user).await?).await
+    }
+
+    pub async fn ws_user_fundings(
+        &self,
+        ws: &WsClient,
+        user: Option<Address>,
+    ) -> Result<WsStream<WsUserFundingsData>> {
+        ws.user_fundings(self.user_or_main_wallet(user).await?)
+            .await
+    }
+
+    pub async fn ws_user_non_funding_ledger_updates(
+        &self,
+        ws: &WsClient,
+        user: Option<Address>,
+    ) -> Result<WsStream<WsUserNonFundingLedgerUpdatesData>> {
+        ws.user_non_funding_ledger_updates(self.user_or_main_wallet(user).await?)
+            .await
+    }
+
+    pub async fn ws_web_data2(
+        &self,
+        ws: &WsClient,
+        user: Option<Address>,
+    ) -> Result<WsStream<WsWebData2>> {
+        ws.web_data2(self.user_or_main_wallet(user).await?).await
+    }
+
+    async fn user_or_main_wallet(&self, user: Option<Address>) -> Result<Address> {
+        match user {
+            Some(user) => Ok(user),
+            None => self.get_evm_main_wallet_address().await,
         }
     }
 
     pub async fn get_api_info(&self) -> Result<ApiInfoResponse> {
         let response = self
             .http_client
-            .get(&format!("{}/api/info", self.base_url))
+            .get(format!("{}/api/info", self.base_url))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .send()
             .await?;
@@ -86,6 +234,25 @@ impl SuperstackApiClient {
         Ok(api_info)
     }
 
+    pub async fn get_evm_main_wallet_address(&self) -> Result<Address> {
+        let api_info = self.get_api_info().await?;
+        let wallet = api_info
+            .user_info
+            .get_evm_main_wallet()
+            .ok_or_else(|| anyhow::anyhow!("No EVM main wallet found for this API key"))?;
+        Ok(wallet.address.parse()?)
+    }
+
+    pub async fn submit_signed(
+        &self,
+        exchange_payload: ExchangePayload,
+    ) -> Result<HypeExchangeResponse> {
+        match &self.exchange_ws {
+            Some(ws) => ws.post_action(&exchange_payload).await,
+            None => self.post_hyperliquid_exchange(exchange_payload).await,
+        }
+    }
+
     async fn post_hyperliquid_exchange(
         &self,
         exchange_payload: ExchangePayload,
@@ -94,7 +261,7 @@ impl SuperstackApiClient {
 
         let response = self
             .http_client
-            .post("https://api.hyperliquid.xyz/exchange")
+            .post(format!("{}/exchange", self.hyperliquid_url))
             .header("Content-Type", "application/json")
             .body(res)
             .send()
@@ -114,8 +281,31 @@ impl SuperstackApiClient {
 
         match response_status {
             HypeExchangeResponseStatus::Ok(response) => Ok(response),
-            HypeExchangeResponseStatus::Err(error) => Err(anyhow::anyhow!(error)),
+            HypeExchangeResponseStatus::Err(error) => Err(HypeExchangeError(error).into()),
+        }
+    }
+
+    async fn post_hyperliquid_info<T: DeserializeOwned>(&self, request: InfoRequest) -> Result<T> {
+        let response = self
+            .http_client
+            .post(format!("{}/info", self.hyperliquid_url))
+            .header("Content-Type", "application/json")
+            .json(&request)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await?;
+            return Err(anyhow::anyhow!(
+                "Request to hyperliquid info endpoint failed with status {}, error: {}",
+                status,
+                text
+            ));
         }
+
+        let info = response.json().await?;
+        Ok(info)
     }
 
     async fn post_wallet_api_exchange(
@@ -123,6 +313,7 @@ impl SuperstackApiClient {
         action: Actions,
         vault_address: Option<Address>,
         expires_after: Option<u64>,
+        nonce: Option<u64>,
     ) -> Result<ExchangeResponse> {
         let full_url = format!("{}/api/exchange", self.base_url);
 
@@ -130,6 +321,7 @@ impl SuperstackApiClient {
             action,
             vault_address,
             expires_after,
+            nonce,
         };
 
         let response = self
@@ -181,14 +373,33 @@ impl SuperstackApiClient {
         expires_after: Option<u64>,
     ) -> Result<HypeExchangeResponse> {
         let exchange_response = self
-            .post_wallet_api_exchange(action, vault_address, expires_after)
+            .post_wallet_api_exchange(action, vault_address, expires_after, None)
             .await?;
         tracing::debug!(
             "exchange_response: {:?}",
             serde_json::to_string(&exchange_response)?
         );
-        self.post_hyperliquid_exchange(exchange_response.payload)
-            .await
+        self.submit_signed(exchange_response.payload).await
+    }
+
+    // Burns `nonce` with a noop action so that a payload signed with it can never execute
+    pub async fn invalidate_pending(
+        &self,
+        nonce: u64,
+        vault_address: Option<Address>,
+    ) -> Result<HypeExchangeResponse> {
+        let exchange_response = self
+            .post_wallet_api_exchange(Actions::Noop, vault_address, None, Some(nonce))
+            .await?;
+        // A noop with any other nonce would leave the pending payload valid
+        if exchange_response.payload.nonce != nonce {
+            return Err(anyhow::anyhow!(
+                "Noop was signed with nonce {} instead of {}",
+                exchange_response.payload.nonce,
+                nonce
+            ));
+        }
+        self.submit_signed(exchange_response.payload).await
     }
 
     pub async fn order(
@@ -206,11 +417,54 @@ impl SuperstackApiClient {
             }
         }
 
+        if bulk_order.builder.is_none() {
+            bulk_order.builder = self.builder.clone();
+        }
+
         let action = Actions::Order(bulk_order);
         self.post_exchange(action, vault_address, expires_after)
             .await
     }
 
+    // Places an exchange-side twap order and returns its twap id
+    pub async fn twap_order(
+        &self,
+        mut twap: TwapRequest,
+        vault_address: Option<Address>,
+        expires_after: Option<u64>,
+    ) -> Result<u64> {
+        twap.sz = float_to_string_for_hashing(twap.sz.parse::<f64>()?);
+
+        let action = Actions::TwapOrder(TwapOrder { twap });
+        let response = self
+            .post_exchange(action, vault_address, expires_after)
+            .await?;
+        match response.data.and_then(|data| data.status) {
+            Some(HypeExchangeDataStatus::Running(running)) => Ok(running.twap_id),
+            Some(HypeExchangeDataStatus::Error(error)) => Err(anyhow::anyhow!(error)),
+            status => Err(anyhow::anyhow!("Unexpected twap order status {:?}", status)),
+        }
+    }
+
+    pub async fn twap_cancel(
+        &self,
+        asset: u32,
+        twap_id: u64,
+        vault_address: Option<Address>,
+        expires_after: Option<u64>,
+    ) -> Result<HypeExchangeResponse> {
+        let action = Actions::TwapCancel(TwapCancel { asset, twap_id });
+        let response = self
+            .post_exchange(action, vault_address, expires_after)
+            .await?;
+        if let Some(HypeExchangeDataStatus::Error(error)) =
+            response.data.as_ref().and_then(|data| data.status.as_ref())
+        {
+            return Err(anyhow::anyhow!(error.clone()));
+        }
+        Ok(response)
+    }
+
     pub async fn cancel(
         &self,
         cancels: BulkCancel,
@@ -282,6 +536,8 @@ impl SuperstackApiClient {
             .await
     }
 
+    // Positive amounts add margin to the open isolated position of `asset`, negative
+    // amounts remove it
     pub async fn update_isolated_margin(
         &self,
         asset: u32,
@@ -289,15 +545,160 @@ impl SuperstackApiClient {
         vault_address: Option<Address>,
         expires_after: Option<u64>,
     ) -> Result<HypeExchangeResponse> {
-        let amount = (amount * 1_000_000.0).round() as i64;
-        let action = Actions::UpdateIsolatedMargin(UpdateIsolatedMargin {
+        let (_, _, _, action) = self
+            .prepare_isolated_margin_update(asset, amount, vault_address)
+            .await?;
+        self.post_exchange(action, vault_address, expires_after)
+            .await
+    }
+
+    // Adds `usd` to the margin of the open isolated position of `asset`
+    pub async fn add_isolated_margin(
+        &self,
+        asset: u32,
+        usd: f64,
+        vault_address: Option<Address>,
+        expires_after: Option<u64>,
+    ) -> Result<HypeIsolatedMarginResult> {
+        if usd.is_nan() || usd <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "Margin amount must be positive, got {}",
+                usd
+            ));
+        }
+        self.adjust_isolated_margin(asset, usd, vault_address, expires_after)
+            .await
+    }
+
+    // Removes `usd` from the margin of the open isolated position of `asset`
+    pub async fn remove_isolated_margin(
+        &self,
+        asset: u32,
+        usd: f64,
+        vault_address: Option<Address>,
+        expires_after: Option<u64>,
+    ) -> Result<HypeIsolatedMarginResult> {
+        if usd.is_nan() || usd <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "Margin amount must be positive, got {}",
+                usd
+            ));
+        }
+        self.adjust_isolated_margin(asset, -usd, vault_address, expires_after)
+            .await
+    }
+
+    async fn adjust_isolated_margin(
+        &self,
+        asset: u32,
+        usd: f64,
+        vault_address: Option<Address>,
+        expires_after: Option<u64>,
+    ) -> Result<HypeIsolatedMarginResult> {
+        let (user, coin, previous_margin, action) = self
+            .prepare_isolated_margin_update(asset, usd, vault_address)
+            .await?;
+        let response = self
+            .post_exchange(action, vault_address, expires_after)
+            .await?;
+        Ok(self
+            .isolated_margin_result(response, user, coin, previous_margin)
+            .await)
+    }
+
+    // Checks the open isolated position of `asset` and builds the update for its side
+    async fn prepare_isolated_margin_update(
+        &self,
+        asset: u32,
+        usd: f64,
+        vault_address: Option<Address>,
+    ) -> Result<(Address, String, Decimal, Actions)> {
+        let (user, coin) = self.isolated_margin_target(asset, vault_address).await?;
+        let state = self.clearinghouse_state(user).await?;
+        let position = state.isolated_position(&coin)?;
+        let update = isolated_margin_update(asset, position, usd)?;
+        Ok((
+            user,
+            coin,
+            position.margin()?,
+            Actions::UpdateIsolatedMargin(update),
+        ))
+    }
+
+    // Adds margin to the open isolated position of `asset` until it is at `leverage`
+    pub async fn top_up_isolated_margin_to_leverage(
+        &self,
+        asset: u32,
+        leverage: f64,
+        vault_address: Option<Address>,
+        expires_after: Option<u64>,
+    ) -> Result<HypeIsolatedMarginResult> {
+        let (user, coin) = self.isolated_margin_target(asset, vault_address).await?;
+        let state = self.clearinghouse_state(user).await?;
+        let position = state.isolated_position(&coin)?;
+        check_top_up_leverage(position, leverage)?;
+        let previous_margin = position.margin()?;
+
+        let action = Actions::TopUpIsolatedOnlyMargin(TopUpIsolatedOnlyMargin {
             asset,
-            is_buy: true,
-            ntli: amount,
+            leverage: float_to_string_for_hashing(leverage),
         });
+        let response = self
+            .post_exchange(action, vault_address, expires_after)
+            .await?;
+        Ok(self
+            .isolated_margin_result(response, user, coin, previous_margin)
+            .await)
+    }
 
-        self.post_exchange(action, vault_address, expires_after)
-            .await
+    // The account holding the position and the coin of `asset`
+    async fn isolated_margin_target(
+        &self,
+        asset: u32,
+        vault_address: Option<Address>,
+    ) -> Result<(Address, String)> {
+        let user = match vault_address {
+            Some(vault_address) => vault_address,
+            None => self.get_evm_main_wallet_address().await?,
+        };
+        let meta = self.meta(None).await?;
+        let coin = meta
+            .universe
+            .get(asset as usize)
+            .ok_or_else(|| anyhow::anyhow!("Unknown perp asset {}", asset))?
+            .name
+            .clone();
+        Ok((user, coin))
+    }
+
+    // The action already executed, a failed follow-up query only leaves the new margin
+    // unknown so that callers do not retry it
+    async fn isolated_margin_result(
+        &self,
+        response: HypeExchangeResponse,
+        user: Address,
+        coin: String,
+        previous_margin: Decimal,
+    ) -> HypeIsolatedMarginResult {
+        let margin = match self.clearinghouse_state(user).await {
+            Ok(state) => state
+                .isolated_position(&coin)
+                .and_then(|position| position.margin()),
+            Err(e) => Err(e),
+        };
+        let margin = match margin {
+            Ok(margin) => Some(margin),
+            Err(e) => {
+                tracing::warn!("failed to query {} margin after the update: {:?}", coin, e);
+                None
+            }
+        };
+        HypeIsolatedMarginResult {
+            response,
+            coin,
+            previous_margin,
+            margin,
+        }
     }
 
     pub async fn usdc_transfer(
@@ -340,10 +741,480 @@ impl SuperstackApiClient {
 
         self.post_exchange(action, None, None).await
     }
+
+    // Sends `amount` of `token` from `source_dex` to `destination_dex` of `destination`,
+    // optionally from a sub-account of the main account
+    pub async fn send_asset(
+        &self,
+        destination: &str,
+        source_dex: &str,
+        destination_dex: &str,
+        token: &str,
+        amount: &str,
+        from_sub_account: Option<Address>,
+    ) -> Result<HypeExchangeResponse> {
+        let action = Actions::SendAsset(SendAsset {
+            destination: destination.to_string(),
+            source_dex: source_dex.to_string(),
+            destination_dex: destination_dex.to_string(),
+            token: token.to_string(),
+            amount: amount.to_string(),
+            from_sub_account: from_sub_account
+                .map(|sub_account| format!("{:#x}", sub_account))
+                .unwrap_or_default(),
+        });
+
+        self.post_exchange(action, None, None).await
+    }
+
+    // Withdraws USDC from the perps account to `destination` on Arbitrum through the bridge
+    pub async fn withdraw(&self, amount: &str, destination: &str) -> Result<HypeWithdrawResult> {
+        let amount_decimal = withdraw_amount(amount)?;
+        let action = Actions::Withdraw3(Withdraw3 {
+            destination: destination.to_string(),
+            amount: amount.to_string(),
+        });
+        let exchange_response = self
+            .post_wallet_api_exchange(action, None, None, None)
+            .await?;
+        let nonce = exchange_response.payload.nonce;
+        let response = self.submit_signed(exchange_response.payload).await?;
+
+        Ok(HypeWithdrawResult {
+            response,
+            nonce,
+            amount: amount_decimal,
+            assumed_fee: WITHDRAW3_ASSUMED_FEE_USDC,
+            fee: None,
+            received: None,
+        })
+    }
+
+    // Authorises `agent_address` to sign actions on behalf of the main wallet. Approving
+    // another agent under the same name replaces it.
+    pub async fn approve_agent(
+        &self,
+        agent_address: Address,
+        name: Option<&str>,
+        valid_until: Option<u64>,
+    ) -> Result<HypeExchangeResponse> {
+        // The expiry is passed to hyperliquid as part of the agent name
+        let agent_name = match (name, valid_until) {
+            (Some(name), Some(valid_until)) => {
+                Some(format!("{} valid_until {}", name, valid_until))
+            }
+            (None, Some(valid_until)) => Some(format!("valid_until {}", valid_until)),
+            (name, None) => name.map(|name| name.to_string()),
+        };
+        let action = Actions::ApproveAgent(ApproveAgent {
+            agent_address: format!("{:#x}", agent_address),
+            agent_name,
+        });
+
+        self.post_exchange(action, None, None).await
+    }
+
+    // Replaces the agent approved under `name` with the zero address
+    pub async fn revoke_agent(&self, name: Option<&str>) -> Result<HypeExchangeResponse> {
+        self.approve_agent(Address::ZERO, name, None).await
+    }
+
+    // Allows `builder` to charge up to `max_fee_rate` on orders, e.g. "0.01%"
+    pub async fn approve_builder_fee(
+        &self,
+        builder: Address,
+        max_fee_rate: &str,
+    ) -> Result<HypeExchangeResponse> {
+        let action = Actions::ApproveBuilderFee(ApproveBuilderFee {
+            max_fee_rate: max_fee_rate.to_string(),
+            builder: format!("{:#x}", builder),
+        });
+
+        self.post_exchange(action, None, None).await
+    }
+
+    pub async fn vault_deposit(
+        &self,
+        vault_address: Address,
+        usd: f64,
+    ) -> Result<HypeVaultTransferResult> {
+        self.vault_transfer(vault_address, true, usd).await
+    }
+
+    // Fails with `HypeVaultTransferError::LockedUp` while the deposit is locked up
+    pub async fn vault_withdraw(
+        &self,
+        vault_address: Address,
+        usd: f64,
+    ) -> Result<HypeVaultTransferResult> {
+        self.vault_transfer(vault_address, false, usd).await
+    }
+
+    async fn vault_transfer(
+        &self,
+        vault_address: Address,
+        is_deposit: bool,
+        usd: f64,
+    ) -> Result<HypeVaultTransferResult> {
+        if usd.is_nan() || usd <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "Vault transfer amount must be positive, got {}",
+                usd
+            ));
+        }
+        let action = Actions::VaultTransfer(VaultTransfer {
+            vault_address: format!("{:#x}", vault_address),
+            is_deposit,
+            usd: (usd * 1_000_000.0).round() as u64,
+        });
+
+        let response = self
+            .post_exchange(action, None, None)
+            .await
+            .map_err(HypeVaultTransferError::from_exchange_error)?;
+        Ok(HypeVaultTransferResult {
+            response,
+            vault_address,
+            is_deposit,
+            usd,
+        })
+    }
+
+    // Returns the address of the new sub-account, use it as `vault_address` to trade on it
+    pub async fn create_sub_account(&self, name: &str) -> Result<Address> {
+        let action = Actions::CreateSubAccount(CreateSubAccount {
+            name: name.to_string(),
+        });
+
+        let response = self.post_exchange(action, None, None).await?;
+        let address = response
+            .raw_data
+            .as_ref()
+            .and_then(|data| data.as_str())
+            .ok_or_else(|| anyhow::anyhow!("No sub-account address in response {:?}", response))?;
+        Ok(address.parse()?)
+    }
+
+    pub async fn rename_sub_account(
+        &self,
+        sub_account: Address,
+        name: &str,
+    ) -> Result<HypeExchangeResponse> {
+        let action = Actions::SubAccountModify(SubAccountModify {
+            sub_account_user: format!("{:#x}", sub_account),
+            name: name.to_string(),
+        });
+
+        self.post_exchange(action, None, None).await
+    }
+
+    // Moves USDC between the perps accounts of the main account and a sub-account
+    pub async fn sub_account_transfer(
+        &self,
+        sub_account: Address,
+        is_deposit: bool,
+        usd: f64,
+    ) -> Result<HypeExchangeResponse> {
+        if usd.is_nan() || usd <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "Sub-account transfer amount must be positive, got {}",
+                usd
+            ));
+        }
+        let action = Actions::SubAccountTransfer(SubAccountTransfer {
+            sub_account_user: format!("{:#x}", sub_account),
+            is_deposit,
+            usd: (usd * 1_000_000.0).round() as u64,
+        });
+
+        self.post_exchange(action, None, None).await
+    }
+
+    // Moves spot tokens between the main account and a sub-account
+    pub async fn sub_account_spot_transfer(
+        &self,
+        sub_account: Address,
+        is_deposit: bool,
+        token: &str,
+        amount: &str,
+    ) -> Result<HypeExchangeResponse> {
+        let action = Actions::SubAccountSpotTransfer(SubAccountSpotTransfer {
+            sub_account_user: format!("{:#x}", sub_account),
+            is_deposit,
+            token: token.to_string(),
+            amount: amount.to_string(),
+        });
+
+        self.post_exchange(action, None, None).await
+    }
+
+    // Moves `amount` HYPE from the spot balance to the staking balance
+    pub async fn staking_deposit(&self, amount: &str) -> Result<HypeExchangeResponse> {
+        let action = Actions::CDeposit(CDeposit {
+            wei: hype_to_wei(amount)?,
+        });
+
+        self.post_exchange(action, None, None).await
+    }
+
+    // Moves `amount` HYPE from the staking balance to the spot balance
+    pub async fn staking_withdraw(&self, amount: &str) -> Result<HypeExchangeResponse> {
+        let action = Actions::CWithdraw(CWithdraw {
+            wei: hype_to_wei(amount)?,
+        });
+
+        self.post_exchange(action, None, None).await
+    }
+
+    // Delegates `amount` HYPE of the staking balance to `validator`
+    pub async fn delegate(&self, validator: Address, amount: &str) -> Result<HypeExchangeResponse> {
+        self.token_delegate(validator, false, amount).await
+    }
+
+    pub async fn undelegate(
+        &self,
+        validator: Address,
+        amount: &str,
+    ) -> Result<HypeExchangeResponse> {
+        self.token_delegate(validator, true, amount).await
+    }
+
+    async fn token_delegate(
+        &self,
+        validator: Address,
+        is_undelegate: bool,
+        amount: &str,
+    ) -> Result<HypeExchangeResponse> {
+        let action = Actions::TokenDelegate(TokenDelegate {
+            validator: format!("{:#x}", validator),
+            is_undelegate,
+            wei: hype_to_wei(amount)?,
+        });
+
+        self.post_exchange(action, None, None).await
+    }
+
+    pub async fn user_funding(
+        &self,
+        user: Address,
+        start_time: u64,
+        end_time: Option<u64>,
+    ) -> Result<Vec<HypeUserFunding>> {
+        let request = InfoRequest::UserFunding {
+            user,
+            start_time,
+            end_time,
+        };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn user_non_funding_ledger_updates(
+        &self,
+        user: Address,
+        start_time: u64,
+        end_time: Option<u64>,
+    ) -> Result<Vec<HypeLedgerUpdate>> {
+        let request = InfoRequest::UserNonFundingLedgerUpdates {
+            user,
+            start_time,
+            end_time,
+        };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn historical_orders(&self, user: Address) -> Result<Vec<HypeHistoricalOrder>> {
+        let request = InfoRequest::HistoricalOrders { user };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn user_fees(&self, user: Address) -> Result<HypeUserFees> {
+        let request = InfoRequest::UserFees { user };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn user_rate_limit(&self, user: Address) -> Result<HypeUserRateLimit> {
+        let request = InfoRequest::UserRateLimit { user };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn all_mids(&self, dex: Option<&str>) -> Result<HashMap<String, Decimal>> {
+        let request = InfoRequest::AllMids {
+            dex: dex.map(|dex| dex.to_string()),
+        };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn meta(&self, dex: Option<&str>) -> Result<HypeMeta> {
+        let request = InfoRequest::Meta {
+            dex: dex.map(|dex| dex.to_string()),
+        };
+        self.post_hyperliquid_info(request).await
+    }
+
+    // Defaults to the EVM main wallet of the API key when no user is given.
+    pub async fn portfolio(&self, user: Option<Address>) -> Result<HypePortfolio> {
+        let user = self.user_or_main_wallet(user).await?;
+        let request = InfoRequest::Portfolio { user };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn vault_details(
+        &self,
+        vault_address: Address,
+        user: Option<Address>,
+    ) -> Result<HypeVaultDetails> {
+        let request = InfoRequest::VaultDetails {
+            vault_address,
+            user,
+        };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn user_vault_equities(&self, user: Address) -> Result<Vec<HypeUserVaultEquity>> {
+        let request = InfoRequest::UserVaultEquities { user };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn sub_accounts(&self, user: Address) -> Result<Vec<HypeSubAccount>> {
+        let request = InfoRequest::SubAccounts { user };
+        // Users without sub-accounts get `null`
+        let sub_accounts: Option<Vec<HypeSubAccount>> = self.post_hyperliquid_info(request).await?;
+        Ok(sub_accounts.unwrap_or_default())
+    }
+
+    // Agents approved with `approve_agent`
+    pub async fn extra_agents(&self, user: Address) -> Result<Vec<HypeExtraAgent>> {
+        let request = InfoRequest::ExtraAgents { user };
+        self.post_hyperliquid_info(request).await
+    }
+
+    // Approved max builder fee in tenths of a basis point
+    pub async fn max_builder_fee(&self, user: Address, builder: Address) -> Result<u64> {
+        let request = InfoRequest::MaxBuilderFee { user, builder };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn twap_slice_fills(&self, user: Address) -> Result<Vec<HypeTwapSliceFill>> {
+        let request = InfoRequest::UserTwapSliceFills { user };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn clearinghouse_state(&self, user: Address) -> Result<HypeClearinghouseState> {
+        let request = InfoRequest::ClearinghouseState { user, dex: None };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn delegations(&self, user: Address) -> Result<Vec<HypeDelegation>> {
+        let request = InfoRequest::Delegations { user };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn delegator_summary(&self, user: Address) -> Result<HypeDelegatorSummary> {
+        let request = InfoRequest::DelegatorSummary { user };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn delegator_rewards(&self, user: Address) -> Result<Vec<HypeDelegatorReward>> {
+        let request = InfoRequest::DelegatorRewards { user };
+        self.post_hyperliquid_info(request).await
+    }
+
+    pub async fn validator_summaries(&self) -> Result<Vec<HypeValidatorSummary>> {
+        self.post_hyperliquid_info(InfoRequest::ValidatorSummaries)
+            .await
+    }
+
+    pub async fn active_asset_data(
+        &self,
+        user: Address,
+        coin: &str,
+    ) -> Result<HypeActiveAssetData> {
+        let request = InfoRequest::ActiveAssetData {
+            user,
+            coin: coin.to_string(),
+        };
+        self.post_hyperliquid_info(request).await
+    }
 }
 
 pub(crate) const WIRE_DECIMALS: u8 = 8;
 
+// Update of `usd` for the side of `position`, removals must leave some margin
+fn isolated_margin_update(
+    asset: u32,
+    position: &HypePosition,
+    usd: f64,
+) -> Result<UpdateIsolatedMargin> {
+    if usd.is_nan() || usd == 0.0 {
+        return Err(anyhow::anyhow!(
+            "Margin amount must be non-zero, got {}",
+            usd
+        ));
+    }
+    let margin = position.margin()?;
+    if usd < 0.0 && Decimal::try_from(-usd)? >= margin {
+        return Err(anyhow::anyhow!(
+            "Cannot remove {} USDC from {} margin of {}",
+            -usd,
+            position.coin,
+            margin
+        ));
+    }
+    Ok(UpdateIsolatedMargin {
+        asset,
+        is_buy: position.is_long()?,
+        ntli: (usd * 1_000_000.0).round() as i64,
+    })
+}
+
+// Topping up only adds margin, so the target must be below the current leverage
+fn check_top_up_leverage(position: &HypePosition, leverage: f64) -> Result<()> {
+    if leverage.is_nan() || leverage < 1.0 || leverage > position.max_leverage as f64 {
+        return Err(anyhow::anyhow!(
+            "Leverage must be between 1 and {} for {}, got {}",
+            position.max_leverage,
+            position.coin,
+            leverage
+        ));
+    }
+    let current_leverage = position.current_leverage()?;
+    if Decimal::try_from(leverage)? >= current_leverage {
+        return Err(anyhow::anyhow!(
+            "{} position is already at {}x leverage, cannot top up to {}x",
+            position.coin,
+            current_leverage.round_dp(2),
+            leverage
+        ));
+    }
+    Ok(())
+}
+
+// Amounts at or below the assumed flat fee would not arrive on Arbitrum
+fn withdraw_amount(amount: &str) -> Result<Decimal> {
+    let amount_decimal: Decimal = amount.parse()?;
+    if amount_decimal <= WITHDRAW3_ASSUMED_FEE_USDC {
+        return Err(anyhow::anyhow!(
+            "Withdraw amount {} does not cover the {} USDC fee",
+            amount,
+            WITHDRAW3_ASSUMED_FEE_USDC
+        ));
+    }
+    Ok(amount_decimal)
+}
+
+// HYPE has 8 decimals on the staking side
+pub(crate) fn hype_to_wei(amount: &str) -> Result<u64> {
+    let amount: Decimal = amount.parse()?;
+    let wei = amount * Decimal::from(100_000_000u64);
+    if wei.is_sign_negative() || wei.is_zero() || !wei.fract().is_zero() {
+        return Err(anyhow::anyhow!(
+            "Invalid HYPE amount {}, must be positive with at most 8 decimals",
+            amount
+        ));
+    }
+    u64::try_from(wei).map_err(|e| anyhow::anyhow!("Invalid HYPE amount {}: {}", amount, e))
+}
+
 pub(crate) fn float_to_string_for_hashing(x: f64) -> String {
     let mut x = format!("{:.*}", WIRE_DECIMALS.into(), x);
     while x.ends_with('0') {
@@ -363,7 +1234,60 @@ pub(crate) fn float_to_string_for_hashing(x: f64) -> String {
 mod tests {
     use super::*;
 
+    fn isolated_position(margin_used: &str) -> HypePosition {
+        serde_json::from_value(serde_json::json!({
+            "coin": "ETH", "szi": "-0.005", "leverage": {"type": "isolated", "value": 20, "rawUsd": "10.9"},
+            "entryPx": "2300.0", "positionValue": "11.5", "unrealizedPnl": "0.0", "returnOnEquity": "0.0",
+            "liquidationPx": "2400.0", "marginUsed": margin_used, "maxLeverage": 50,
+            "cumFunding": {"allTime": "0.0", "sinceOpen": "0.0", "sinceChange": "0.0"}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_isolated_margin_validation() {
+        // 11.5 / 1.15 = 10x
+        let position = isolated_position("1.15");
+
+        let update = isolated_margin_update(1, &position, 2.5).unwrap();
+        assert!(!update.is_buy);
+        assert_eq!(update.ntli, 2_500_000);
+        let update = isolated_margin_update(1, &position, -1.0).unwrap();
+        assert_eq!(update.ntli, -1_000_000);
+        assert!(isolated_margin_update(1, &position, 0.0).is_err());
+        assert!(isolated_margin_update(1, &position, f64::NAN).is_err());
+        assert!(isolated_margin_update(1, &position, -1.15).is_err());
+        assert!(isolated_margin_update(1, &position, -2.0).is_err());
+
+        assert!(check_top_up_leverage(&position, 5.0).is_ok());
+        assert!(check_top_up_leverage(&position, 10.0).is_err());
+        assert!(check_top_up_leverage(&position, 20.0).is_err());
+        assert!(check_top_up_leverage(&position, 0.5).is_err());
+        assert!(check_top_up_leverage(&position, f64::NAN).is_err());
+        assert!(check_top_up_leverage(&isolated_position("0.0"), 5.0).is_err());
+    }
+
+    #[test]
+    fn test_withdraw_amount() {
+        assert_eq!(withdraw_amount("10").unwrap(), Decimal::TEN);
+        assert!(withdraw_amount("1").is_err());
+        assert!(withdraw_amount("0.5").is_err());
+        assert!(withdraw_amount("-3").is_err());
+        assert!(withdraw_amount("ten").is_err());
+    }
+
+    #[test]
+    fn test_hype_to_wei() {
+        assert_eq!(hype_to_wei("1").unwrap(), 100_000_000);
+        assert_eq!(hype_to_wei("0.00000001").unwrap(), 1);
+        assert_eq!(hype_to_wei("12.5").unwrap(), 1_250_000_000);
+        assert!(hype_to_wei("0.000000001").is_err());
+        assert!(hype_to_wei("-1").is_err());
+        assert!(hype_to_wei("0").is_err());
+    }
+
     #[tokio::test]
+    #[ignore = "requires WALLET_API_BASE_URL and WALLET_API_KEY"]
     async fn test_get_api_info() {
         dotenv::dotenv().ok();
 
@@ -372,7 +1296,7 @@ mod tests {
             std::env::var("WALLET_API_BASE_URL").expect("WALLET_API_BASE_URL env var not set");
         let api_key = std::env::var("WALLET_API_KEY").expect("WALLET_API_KEY env var not set");
 
-        let client = SuperstackApiClient::new(&base_url, &api_key);
+        let client = SuperstackApiClient::with_base_url(&api_key, &base_url);
 
         let result = client.get_api_info().await;
 
@@ -393,6 +1317,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "requires WALLET_API_BASE_URL"]
     async fn test_get_api_info_invalid_api_key() {
         dotenv::dotenv().ok();
 
@@ -401,7 +1326,7 @@ mod tests {
             std::env::var("WALLET_API_BASE_URL").expect("WALLET_API_BASE_URL env var not set");
         let api_key = "invalid_api_key";
 
-        let client = SuperstackApiClient::new(&base_url, &api_key);
+        let client = SuperstackApiClient::with_base_url(api_key, &base_url);
         let result = client.get_api_info().await;
         match result {
             Err(e) => {
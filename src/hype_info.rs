@@ -0,0 +1,1240 @@
+use std::str::FromStr;
+
+use alloy::primitives::Address;
+use anyhow::Result;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::actions::OrderRequest;
+use crate::ws_messages::WsFill;
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+pub enum InfoRequest {
+    #[serde(rename_all = "camelCase")]
+    UserFunding {
+        user: Address,
+        start_time: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        end_time: Option<u64>,
+    },
+    #[serde(rename_all = "camelCase")]
+    UserNonFundingLedgerUpdates {
+        user: Address,
+        start_time: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        end_time: Option<u64>,
+    },
+    HistoricalOrders {
+        user: Address,
+    },
+    UserFees {
+        user: Address,
+    },
+    UserRateLimit {
+        user: Address,
+    },
+    AllMids {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        dex: Option<String>,
+    },
+    Portfolio {
+        user: Address,
+    },
+    #[serde(rename_all = "camelCase")]
+    VaultDetails {
+        vault_address: Address,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        user: Option<Address>,
+    },
+    UserVaultEquities {
+        user: Address,
+    },
+    SubAccounts {
+        user: Address,
+    },
+    ActiveAssetData {
+        user: Address,
+        coin: String,
+    },
+    Meta {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        dex: Option<String>,
+    },
+    ExtraAgents {
+        user: Address,
+    },
+    MaxBuilderFee {
+        user: Address,
+        builder: Address,
+    },
+    UserTwapSliceFills {
+        user: Address,
+    },
+    ClearinghouseState {
+        user: Address,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        dex: Option<String>,
+    },
+    Delegations {
+        user: Address,
+    },
+    DelegatorSummary {
+        user: Address,
+    },
+    DelegatorRewards {
+        user: Address,
+    },
+    ValidatorSummaries,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeFundingDelta {
+    pub coin: String,
+    pub usdc: String,
+    pub szi: String,
+    pub funding_rate: String,
+    #[serde(default)]
+    pub n_samples: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HypeUserFunding {
+    pub time: u64,
+    pub hash: String,
+    pub delta: HypeFundingDelta,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HypeLiquidatedPosition {
+    pub coin: String,
+    pub szi: String,
+}
+
+// `usdc_transfer` shows up as `InternalTransfer`, `spot_transfer` as `SpotTransfer`
+// and `usd_class_transfer` as `AccountClassTransfer`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+pub enum HypeLedgerDelta {
+    Deposit {
+        usdc: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Withdraw {
+        usdc: String,
+        nonce: u64,
+        fee: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    InternalTransfer {
+        usdc: String,
+        user: String,
+        destination: String,
+        fee: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    SubAccountTransfer {
+        usdc: String,
+        user: String,
+        destination: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    SpotTransfer {
+        token: String,
+        amount: String,
+        usdc_value: String,
+        user: String,
+        destination: String,
+        fee: String,
+        #[serde(default)]
+        native_token_fee: Option<String>,
+        #[serde(default)]
+        nonce: Option<u64>,
+    },
+    #[serde(rename_all = "camelCase")]
+    AccountClassTransfer {
+        usdc: String,
+        to_perp: bool,
+    },
+    SpotGenesis {
+        token: String,
+        amount: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Liquidation {
+        liquidated_ntl_pos: String,
+        account_value: String,
+        leverage_type: String,
+        liquidated_positions: Vec<HypeLiquidatedPosition>,
+    },
+    VaultCreate {
+        vault: String,
+        usdc: String,
+        fee: String,
+    },
+    VaultDeposit {
+        vault: String,
+        usdc: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    VaultWithdraw {
+        vault: String,
+        user: String,
+        requested_usd: String,
+        commission: String,
+        closing_cost: String,
+        basis: String,
+        net_withdrawn_usd: String,
+    },
+    VaultDistribution {
+        vault: String,
+        usdc: String,
+    },
+    VaultLeaderCommission {
+        user: String,
+        usdc: String,
+    },
+    RewardsClaim {
+        amount: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    CStakingTransfer {
+        token: String,
+        amount: String,
+        is_deposit: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    Send {
+        user: String,
+        destination: String,
+        source_dex: String,
+        destination_dex: String,
+        token: String,
+        amount: String,
+        usdc_value: String,
+        fee: String,
+        #[serde(default)]
+        native_token_fee: Option<String>,
+        #[serde(default)]
+        nonce: Option<u64>,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HypeLedgerUpdate {
+    pub time: u64,
+    pub hash: String,
+    pub delta: HypeLedgerDelta,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeOrderDetails {
+    pub coin: String,
+    pub side: String,
+    pub limit_px: String,
+    pub sz: String,
+    pub oid: u64,
+    pub timestamp: u64,
+    pub trigger_condition: String,
+    pub is_trigger: bool,
+    pub trigger_px: String,
+    #[serde(default)]
+    pub children: Vec<HypeOrderDetails>,
+    pub is_position_tpsl: bool,
+    pub reduce_only: bool,
+    pub order_type: String,
+    pub orig_sz: String,
+    pub tif: Option<String>,
+    pub cloid: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HypeOrderStatus {
+    Open,
+    Filled,
+    Canceled,
+    Triggered,
+    Rejected,
+    MarginCanceled,
+    VaultWithdrawalCanceled,
+    OpenInterestCapCanceled,
+    SelfTradeCanceled,
+    ReduceOnlyCanceled,
+    SiblingFilledCanceled,
+    DelistedCanceled,
+    LiquidatedCanceled,
+    ScheduledCancel,
+    TickRejected,
+    MinTradeNtlRejected,
+    PerpMarginRejected,
+    ReduceOnlyRejected,
+    BadAloPxRejected,
+    IocCancelRejected,
+    BadTriggerPxRejected,
+    MarketOrderNoLiquidityRejected,
+    PositionIncreaseAtOpenInterestCapRejected,
+    PositionFlipAtOpenInterestCapRejected,
+    TooAggressiveAtOpenInterestCapRejected,
+    OpenInterestIncreaseRejected,
+    InsufficientSpotBalanceRejected,
+    OracleRejected,
+    PerpMaxPositionRejected,
+    #[serde(other)]
+    Unknown,
+}
+
+impl HypeOrderStatus {
+    pub fn is_rejected(&self) -> bool {
+        matches!(
+            self,
+            Self::Rejected
+                | Self::TickRejected
+                | Self::MinTradeNtlRejected
+                | Self::PerpMarginRejected
+                | Self::ReduceOnlyRejected
+                | Self::BadAloPxRejected
+                | Self::IocCancelRejected
+                | Self::BadTriggerPxRejected
+                | Self::MarketOrderNoLiquidityRejected
+                | Self::PositionIncreaseAtOpenInterestCapRejected
+                | Self::PositionFlipAtOpenInterestCapRejected
+                | Self::TooAggressiveAtOpenInterestCapRejected
+                | Self::OpenInterestIncreaseRejected
+                | Self::InsufficientSpotBalanceRejected
+                | Self::OracleRejected
+                | Self::PerpMaxPositionRejected
+        )
+    }
+
+    pub fn is_canceled(&self) -> bool {
+        matches!(
+            self,
+            Self::Canceled
+                | Self::MarginCanceled
+                | Self::VaultWithdrawalCanceled
+                | Self::OpenInterestCapCanceled
+                | Self::SelfTradeCanceled
+                | Self::ReduceOnlyCanceled
+                | Self::SiblingFilledCanceled
+                | Self::DelistedCanceled
+                | Self::LiquidatedCanceled
+                | Self::ScheduledCancel
+        )
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeHistoricalOrder {
+    pub order: HypeOrderDetails,
+    pub status: HypeOrderStatus,
+    pub status_timestamp: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeDailyUserVolume {
+    pub date: String,
+    pub user_cross: String,
+    pub user_add: String,
+    pub exchange: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeVipTier {
+    pub ntl_cutoff: String,
+    pub cross: String,
+    pub add: String,
+    #[serde(default)]
+    pub spot_cross: Option<String>,
+    #[serde(default)]
+    pub spot_add: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeMakerTier {
+    pub maker_fraction_cutoff: String,
+    pub add: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HypeFeeTiers {
+    pub vip: Vec<HypeVipTier>,
+    pub mm: Vec<HypeMakerTier>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeFeeSchedule {
+    pub cross: String,
+    pub add: String,
+    #[serde(default)]
+    pub spot_cross: Option<String>,
+    #[serde(default)]
+    pub spot_add: Option<String>,
+    pub tiers: HypeFeeTiers,
+    pub referral_discount: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeUserFees {
+    pub daily_user_vlm: Vec<HypeDailyUserVolume>,
+    pub fee_schedule: HypeFeeSchedule,
+    pub user_cross_rate: String,
+    pub user_add_rate: String,
+    #[serde(default)]
+    pub user_spot_cross_rate: Option<String>,
+    #[serde(default)]
+    pub user_spot_add_rate: Option<String>,
+    pub active_referral_discount: String,
+}
+
+impl HypeUserFees {
+    // Fee tiers are based on the trailing 14 day volume, makers and takers combined.
+    pub fn fourteen_day_volume(&self) -> f64 {
+        self.daily_user_vlm
+            .iter()
+            .rev()
+            .take(14)
+            .map(|day| {
+                day.user_cross.parse::<f64>().unwrap_or(0.0)
+                    + day.user_add.parse::<f64>().unwrap_or(0.0)
+            })
+            .sum()
+    }
+
+    // 0 is the base tier, 1 is the first vip tier and so on.
+    pub fn volume_tier(&self) -> usize {
+        let volume = self.fourteen_day_volume();
+        self.fee_schedule
+            .tiers
+            .vip
+            .iter()
+            .take_while(|tier| tier.ntl_cutoff.parse::<f64>().unwrap_or(f64::MAX) <= volume)
+            .count()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeUserRateLimit {
+    pub cum_vlm: String,
+    pub n_requests_used: u64,
+    pub n_requests_cap: u64,
+}
+
+impl HypeUserRateLimit {
+    pub fn requests_remaining(&self) -> u64 {
+        self.n_requests_cap.saturating_sub(self.n_requests_used)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HypePortfolioPeriod {
+    // (unix timestamp in milliseconds, value)
+    pub account_value_history: Vec<(u64, String)>,
+    pub pnl_history: Vec<(u64, String)>,
+    pub vlm: String,
+}
+
+// The perp_* windows only include the perp account, the others combine spot and perp.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(from = "Vec<(String, HypePortfolioPeriod)>")]
+pub struct HypePortfolio {
+    pub day: HypePortfolioPeriod,
+    pub week: HypePortfolioPeriod,
+    pub month: HypePortfolioPeriod,
+    pub all_time: HypePortfolioPeriod,
+    pub perp_day: HypePortfolioPeriod,
+    pub perp_week: HypePortfolioPeriod,
+    pub perp_month: HypePortfolioPeriod,
+    pub perp_all_time: HypePortfolioPeriod,
+}
+
+impl From<Vec<(String, HypePortfolioPeriod)>> for HypePortfolio {
+    fn from(periods: Vec<(String, HypePortfolioPeriod)>) -> Self {
+        let mut portfolio = Self::default();
+        for (window, period) in periods {
+            match window.as_str() {
+                "day" => portfolio.day = period,
+                "week" => portfolio.week = period,
+                "month" => portfolio.month = period,
+                "allTime" => portfolio.all_time = period,
+                "perpDay" => portfolio.perp_day = period,
+                "perpWeek" => portfolio.perp_week = period,
+                "perpMonth" => portfolio.perp_month = period,
+                "perpAllTime" => portfolio.perp_all_time = period,
+                _ => {}
+            }
+        }
+        portfolio
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeVaultFollower {
+    pub user: String,
+    pub vault_equity: String,
+    pub pnl: String,
+    pub all_time_pnl: String,
+    pub days_following: u64,
+    pub vault_entry_time: u64,
+    pub lockup_until: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeVaultDetails {
+    pub name: String,
+    pub vault_address: String,
+    pub leader: String,
+    pub description: String,
+    pub portfolio: HypePortfolio,
+    pub apr: f64,
+    // Only set when the details were queried for a user that follows the vault
+    pub follower_state: Option<HypeVaultFollower>,
+    pub leader_fraction: f64,
+    pub leader_commission: f64,
+    pub followers: Vec<HypeVaultFollower>,
+    pub max_distributable: f64,
+    pub max_withdrawable: f64,
+    pub is_closed: bool,
+    pub allow_deposits: bool,
+}
+
+impl HypeVaultDetails {
+    // Latest account value of the vault
+    pub fn tvl(&self) -> Option<Decimal> {
+        self.portfolio
+            .day
+            .account_value_history
+            .last()
+            .and_then(|(_, value)| Decimal::from_str(value).ok())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeUserVaultEquity {
+    pub vault_address: String,
+    pub equity: String,
+    #[serde(default)]
+    pub locked_until_timestamp: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeMarginSummary {
+    pub account_value: String,
+    pub total_ntl_pos: String,
+    pub total_raw_usd: String,
+    pub total_margin_used: String,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+pub enum HypeLeverage {
+    Cross {
+        value: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    Isolated {
+        value: u32,
+        raw_usd: String,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeCumulativeFunding {
+    pub all_time: String,
+    pub since_open: String,
+    pub since_change: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypePosition {
+    pub coin: String,
+    pub szi: String,
+    pub leverage: HypeLeverage,
+    pub entry_px: Option<String>,
+    pub position_value: String,
+    pub unrealized_pnl: String,
+    pub return_on_equity: String,
+    pub liquidation_px: Option<String>,
+    pub margin_used: String,
+    pub max_leverage: u32,
+    pub cum_funding: HypeCumulativeFunding,
+}
+
+impl HypePosition {
+    pub fn is_long(&self) -> Result<bool> {
+        Ok(Decimal::from_str(&self.szi)?.is_sign_positive())
+    }
+
+    pub fn margin(&self) -> Result<Decimal> {
+        Ok(Decimal::from_str(&self.margin_used)?)
+    }
+
+    // Position value over margin used
+    pub fn current_leverage(&self) -> Result<Decimal> {
+        let margin = self.margin()?;
+        if margin.is_zero() {
+            return Err(anyhow::anyhow!("{} position has no margin", self.coin));
+        }
+        Ok(Decimal::from_str(&self.position_value)? / margin)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HypeAssetPosition {
+    #[serde(rename = "type")]
+    pub position_type: String,
+    pub position: HypePosition,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeClearinghouseState {
+    pub margin_summary: HypeMarginSummary,
+    pub cross_margin_summary: HypeMarginSummary,
+    pub cross_maintenance_margin_used: String,
+    pub withdrawable: String,
+    pub asset_positions: Vec<HypeAssetPosition>,
+    pub time: u64,
+}
+
+impl HypeClearinghouseState {
+    pub fn position(&self, coin: &str) -> Option<&HypePosition> {
+        self.asset_positions
+            .iter()
+            .map(|asset_position| &asset_position.position)
+            .find(|position| position.coin == coin)
+    }
+
+    pub fn isolated_position(&self, coin: &str) -> Result<&HypePosition> {
+        let position = self
+            .position(coin)
+            .ok_or_else(|| anyhow::anyhow!("No open {} position", coin))?;
+        if !matches!(position.leverage, HypeLeverage::Isolated { .. }) {
+            return Err(anyhow::anyhow!("{} position is not isolated", coin));
+        }
+        Ok(position)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeSpotBalance {
+    pub coin: String,
+    pub token: u32,
+    pub total: String,
+    pub hold: String,
+    pub entry_ntl: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HypeSpotClearinghouseState {
+    pub balances: Vec<HypeSpotBalance>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeSubAccount {
+    pub name: String,
+    pub sub_account_user: String,
+    pub master: String,
+    pub clearinghouse_state: HypeClearinghouseState,
+    pub spot_state: HypeSpotClearinghouseState,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeTwapSliceFill {
+    pub fill: WsFill,
+    pub twap_id: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeDelegation {
+    pub validator: Address,
+    pub amount: String,
+    // Undelegating is possible after this unix timestamp in milliseconds
+    pub locked_until_timestamp: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeDelegatorSummary {
+    pub delegated: String,
+    pub undelegated: String,
+    pub total_pending_withdrawal: String,
+    pub n_pending_withdrawals: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeDelegatorReward {
+    pub time: u64,
+    // "delegation" or "commission"
+    pub source: String,
+    pub total_amount: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeValidatorStats {
+    pub uptime_fraction: String,
+    pub predicted_apr: String,
+    pub n_samples: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeValidatorSummary {
+    pub validator: Address,
+    pub signer: Address,
+    pub name: String,
+    pub description: String,
+    pub n_recent_blocks: u64,
+    // HYPE in units of 1e-8
+    pub stake: u64,
+    pub is_jailed: bool,
+    pub unjailable_after: Option<u64>,
+    pub is_active: bool,
+    pub commission: String,
+    // Keyed by "day", "week" and "month"
+    pub stats: Vec<(String, HypeValidatorStats)>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeExtraAgent {
+    pub name: String,
+    pub address: Address,
+    // Unix timestamp in milliseconds after which the agent can no longer sign
+    pub valid_until: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeActiveAssetData {
+    pub user: String,
+    pub coin: String,
+    pub leverage: HypeLeverage,
+    // [long, short]
+    pub max_trade_szs: [String; 2],
+    // [long, short]
+    pub available_to_trade: [String; 2],
+    pub mark_px: String,
+}
+
+impl HypeActiveAssetData {
+    pub fn max_trade_sz(&self, is_buy: bool) -> Result<Decimal, rust_decimal::Error> {
+        let index = if is_buy { 0 } else { 1 };
+        Decimal::from_str(&self.max_trade_szs[index])
+    }
+
+    pub fn available_to_trade(&self, is_buy: bool) -> Result<Decimal, rust_decimal::Error> {
+        let index = if is_buy { 0 } else { 1 };
+        Decimal::from_str(&self.available_to_trade[index])
+    }
+
+    // Clamps `order.sz` to the max trade size of its side, rounded down to `sz_decimals`.
+    // Returns true when the size was reduced, errors without touching the order when
+    // nothing can be traded on that side.
+    pub fn clamp_order_sz(&self, order: &mut OrderRequest, sz_decimals: u32) -> Result<bool> {
+        let sz = Decimal::from_str(&order.sz)?;
+        let max_sz = self
+            .max_trade_sz(order.is_buy)?
+            .round_dp_with_strategy(sz_decimals, RoundingStrategy::ToZero);
+        if sz <= max_sz {
+            return Ok(false);
+        }
+        if max_sz.is_zero() {
+            return Err(anyhow::anyhow!(
+                "Max {} trade size for {} rounds to zero",
+                if order.is_buy { "long" } else { "short" },
+                self.coin
+            ));
+        }
+        order.sz = max_sz.normalize().to_string();
+        Ok(true)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeMarginTier {
+    pub lower_bound: String,
+    pub max_leverage: u32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeMarginTable {
+    pub description: String,
+    pub margin_tiers: Vec<HypeMarginTier>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HypeMarginRequirement {
+    pub initial_margin: Decimal,
+    pub maintenance_margin: Decimal,
+}
+
+impl HypeMarginTable {
+    // Table ids below 50 are not listed in the meta, they are a single tier whose max
+    // leverage is the id.
+    pub fn single_tier(max_leverage: u32) -> Self {
+        Self {
+            description: format!("{}x", max_leverage),
+            margin_tiers: vec![HypeMarginTier {
+                lower_bound: "0.0".to_string(),
+                max_leverage,
+            }],
+        }
+    }
+
+    // (lower bound, max leverage, maintenance margin rate, maintenance deduction) of
+    // every tier, ordered by lower bound.
+    fn tiers(&self) -> Result<Vec<(Decimal, u32, Decimal, Decimal)>> {
+        let mut tiers = Vec::with_capacity(self.margin_tiers.len());
+        let mut previous_rate = Decimal::ZERO;
+        let mut deduction = Decimal::ZERO;
+        for tier in &self.margin_tiers {
+            if tier.max_leverage == 0 {
+                return Err(anyhow::anyhow!("Margin tier with zero max leverage"));
+            }
+            let lower_bound = Decimal::from_str(&tier.lower_bound)?;
+            let rate = Decimal::ONE / Decimal::from(2 * tier.max_leverage);
+            if !tiers.is_empty() {
+                deduction += lower_bound * (rate - previous_rate);
+            }
+            tiers.push((lower_bound, tier.max_leverage, rate, deduction));
+            previous_rate = rate;
+        }
+        if tiers.is_empty() {
+            return Err(anyhow::anyhow!("Margin table has no tiers"));
+        }
+        Ok(tiers)
+    }
+
+    fn tier_for(&self, notional: Decimal) -> Result<(Decimal, u32, Decimal, Decimal)> {
+        let tiers = self.tiers()?;
+        let tier = tiers
+            .iter()
+            .rev()
+            .find(|(lower_bound, ..)| *lower_bound <= notional)
+            .unwrap_or(&tiers[0]);
+        Ok(*tier)
+    }
+
+    pub fn max_leverage(&self, notional: Decimal) -> Result<u32> {
+        let (_, max_leverage, ..) = self.tier_for(notional.abs())?;
+        Ok(max_leverage)
+    }
+
+    // maintenance margin = notional * maintenance margin rate - maintenance deduction,
+    // the maintenance margin rate of a tier is half of the initial margin at its max leverage.
+    pub fn maintenance_margin(&self, notional: Decimal) -> Result<Decimal> {
+        let notional = notional.abs();
+        let (_, _, rate, deduction) = self.tier_for(notional)?;
+        Ok(notional * rate - deduction)
+    }
+
+    pub fn margin_requirement(
+        &self,
+        notional: Decimal,
+        leverage: u32,
+    ) -> Result<HypeMarginRequirement> {
+        let notional = notional.abs();
+        let max_leverage = self.max_leverage(notional)?;
+        if leverage == 0 || leverage > max_leverage {
+            return Err(anyhow::anyhow!(
+                "Leverage {} is not allowed for notional {}, max leverage is {}",
+                leverage,
+                notional,
+                max_leverage
+            ));
+        }
+        Ok(HypeMarginRequirement {
+            initial_margin: notional / Decimal::from(leverage),
+            maintenance_margin: self.maintenance_margin(notional)?,
+        })
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeAssetMeta {
+    pub name: String,
+    pub sz_decimals: u32,
+    pub max_leverage: u32,
+    #[serde(default)]
+    pub margin_table_id: Option<u32>,
+    #[serde(default)]
+    pub only_isolated: bool,
+    #[serde(default)]
+    pub is_delisted: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeMeta {
+    pub universe: Vec<HypeAssetMeta>,
+    #[serde(default)]
+    pub margin_tables: Vec<(u32, HypeMarginTable)>,
+}
+
+impl HypeMeta {
+    pub fn asset(&self, coin: &str) -> Option<(u32, &HypeAssetMeta)> {
+        self.universe
+            .iter()
+            .enumerate()
+            .find(|(_, asset)| asset.name == coin)
+            .map(|(index, asset)| (index as u32, asset))
+    }
+
+    pub fn margin_table(&self, margin_table_id: u32) -> Option<HypeMarginTable> {
+        self.margin_tables
+            .iter()
+            .find(|(id, _)| *id == margin_table_id)
+            .map(|(_, table)| table.clone())
+            .or_else(|| {
+                (margin_table_id < 50).then(|| HypeMarginTable::single_tier(margin_table_id))
+            })
+    }
+
+    // Falls back to a single tier at the asset's max leverage for assets without a margin table.
+    pub fn asset_margin_table(&self, coin: &str) -> Option<HypeMarginTable> {
+        let (_, asset) = self.asset(coin)?;
+        match asset.margin_table_id {
+            Some(id) => self.margin_table(id),
+            None => Some(HypeMarginTable::single_tier(asset.max_leverage)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_info_request_serialization() {
+        let request = InfoRequest::UserFunding {
+            user: Address::ZERO,
+            start_time: 1_700_000_000_000,
+            end_time: None,
+        };
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value["type"], "userFunding");
+        assert_eq!(value["startTime"], 1_700_000_000_000u64);
+        assert!(value.get("endTime").is_none());
+    }
+
+    #[test]
+    fn test_ledger_updates_deserialization() {
+        let json = r#"[
+            {"time": 1, "hash": "0x01", "delta": {"type": "deposit", "usdc": "100.0"}},
+            {"time": 2, "hash": "0x02", "delta": {"type": "accountClassTransfer", "usdc": "5.0", "toPerp": false}},
+            {"time": 3, "hash": "0x03", "delta": {"type": "internalTransfer", "usdc": "0.01", "user": "0xaa", "destination": "0xbb", "fee": "0.0"}},
+            {"time": 4, "hash": "0x04", "delta": {"type": "somethingNew", "foo": "bar"}}
+        ]"#;
+        let updates: Vec<HypeLedgerUpdate> = serde_json::from_str(json).unwrap();
+        assert!(matches!(updates[0].delta, HypeLedgerDelta::Deposit { .. }));
+        assert!(matches!(
+            updates[1].delta,
+            HypeLedgerDelta::AccountClassTransfer { to_perp: false, .. }
+        ));
+        assert!(matches!(
+            updates[2].delta,
+            HypeLedgerDelta::InternalTransfer { .. }
+        ));
+        assert!(matches!(updates[3].delta, HypeLedgerDelta::Unknown));
+    }
+
+    #[test]
+    fn test_historical_orders_deserialization() {
+        let json = r#"[{
+            "order": {
+                "coin": "BTC", "side": "B", "limitPx": "100000.0", "sz": "0.0", "oid": 42,
+                "timestamp": 1700000000000, "triggerCondition": "N/A", "isTrigger": false,
+                "triggerPx": "0.0", "children": [], "isPositionTpsl": false, "reduceOnly": false,
+                "orderType": "Limit", "origSz": "0.0001", "tif": "Gtc", "cloid": null
+            },
+            "status": "marginCanceled",
+            "statusTimestamp": 1700000001000
+        }]"#;
+        let orders: Vec<HypeHistoricalOrder> = serde_json::from_str(json).unwrap();
+        assert_eq!(orders[0].order.oid, 42);
+        assert_eq!(orders[0].status, HypeOrderStatus::MarginCanceled);
+        assert!(orders[0].status.is_canceled());
+        assert!(!orders[0].status.is_rejected());
+    }
+
+    #[test]
+    fn test_user_fees_volume_tier() {
+        let json = r#"{
+            "dailyUserVlm": [
+                {"date": "2025-01-01", "userCross": "4000000.0", "userAdd": "1500000.0", "exchange": "1.0"},
+                {"date": "2025-01-02", "userCross": "0.0", "userAdd": "0.0", "exchange": "1.0"}
+            ],
+            "feeSchedule": {
+                "cross": "0.00045", "add": "0.00015",
+                "tiers": {
+                    "vip": [
+                        {"ntlCutoff": "5000000.0", "cross": "0.0004", "add": "0.00012"},
+                        {"ntlCutoff": "25000000.0", "cross": "0.00035", "add": "0.00008"}
+                    ],
+                    "mm": [{"makerFractionCutoff": "0.005", "add": "-0.00001"}]
+                },
+                "referralDiscount": "0.04"
+            },
+            "userCrossRate": "0.0004",
+            "userAddRate": "0.00012",
+            "activeReferralDiscount": "0.0"
+        }"#;
+        let fees: HypeUserFees = serde_json::from_str(json).unwrap();
+        assert_eq!(fees.fourteen_day_volume(), 5_500_000.0);
+        assert_eq!(fees.volume_tier(), 1);
+    }
+
+    #[test]
+    fn test_portfolio_deserialization() {
+        let json = r#"[
+            ["day", {"accountValueHistory": [[1700000000000, "100.0"]], "pnlHistory": [[1700000000000, "0.0"]], "vlm": "0.0"}],
+            ["perpAllTime", {"accountValueHistory": [], "pnlHistory": [[1600000000000, "-5.5"]], "vlm": "1000.0"}]
+        ]"#;
+        let portfolio: HypePortfolio = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            portfolio.day.account_value_history,
+            vec![(1700000000000, "100.0".to_string())]
+        );
+        assert_eq!(portfolio.perp_all_time.pnl_history[0].1, "-5.5");
+        assert!(portfolio.week.pnl_history.is_empty());
+    }
+
+    #[test]
+    fn test_vault_details_deserialization() {
+        let json = r#"{
+            "name": "Test Vault",
+            "vaultAddress": "0xdfc24b077bc1425ad1dea75bcb6f8158e10df303",
+            "leader": "0x677d831aef5328190852e24f13c46cac05f984e7",
+            "description": "",
+            "portfolio": [
+                ["day", {"accountValueHistory": [[1, "10.0"], [2, "12.5"]], "pnlHistory": [], "vlm": "0.0"}]
+            ],
+            "apr": 0.12,
+            "followerState": null,
+            "leaderFraction": 0.1,
+            "leaderCommission": 0,
+            "followers": [{
+                "user": "0x0000000000000000000000000000000000000001",
+                "vaultEquity": "2.5", "pnl": "0.5", "allTimePnl": "0.5",
+                "daysFollowing": 3, "vaultEntryTime": 1, "lockupUntil": 86400001
+            }],
+            "maxDistributable": 0.0,
+            "maxWithdrawable": 1.0,
+            "isClosed": false,
+            "relationship": {"type": "normal"},
+            "allowDeposits": true,
+            "alwaysCloseOnWithdraw": false
+        }"#;
+        let vault: HypeVaultDetails = serde_json::from_str(json).unwrap();
+        assert_eq!(vault.tvl(), Some(Decimal::new(125, 1)));
+        assert_eq!(vault.followers[0].lockup_until, 86400001);
+        assert!(vault.follower_state.is_none());
+    }
+
+    #[test]
+    fn test_sub_accounts_deserialization() {
+        let json = r#"[{
+            "name": "strategy-1",
+            "subAccountUser": "0x035605fc2f24d65300227189025e90a0d947f16c",
+            "master": "0x8c967e73e7b15087c42a10d344cff4c96d877f1d",
+            "clearinghouseState": {
+                "marginSummary": {"accountValue": "29.78", "totalNtlPos": "11.5", "totalRawUsd": "18.28", "totalMarginUsed": "0.57"},
+                "crossMarginSummary": {"accountValue": "29.78", "totalNtlPos": "11.5", "totalRawUsd": "18.28", "totalMarginUsed": "0.57"},
+                "crossMaintenanceMarginUsed": "0.28",
+                "withdrawable": "29.2",
+                "assetPositions": [{
+                    "type": "oneWay",
+                    "position": {
+                        "coin": "ETH", "szi": "0.005", "leverage": {"type": "isolated", "value": 20, "rawUsd": "-10.9"},
+                        "entryPx": "2300.0", "positionValue": "11.5", "unrealizedPnl": "0.0", "returnOnEquity": "0.0",
+                        "liquidationPx": "2200.0", "marginUsed": "0.57", "maxLeverage": 50,
+                        "cumFunding": {"allTime": "0.0", "sinceOpen": "0.0", "sinceChange": "0.0"}
+                    }
+                }],
+                "time": 1733968369395
+            },
+            "spotState": {"balances": [{"coin": "USDC", "token": 0, "total": "0.22", "hold": "0.0", "entryNtl": "0.0"}]}
+        }]"#;
+        let sub_accounts: Vec<HypeSubAccount> = serde_json::from_str(json).unwrap();
+        assert_eq!(sub_accounts[0].name, "strategy-1");
+        assert_eq!(
+            sub_accounts[0].clearinghouse_state.asset_positions[0]
+                .position
+                .leverage,
+            HypeLeverage::Isolated {
+                value: 20,
+                raw_usd: "-10.9".to_string()
+            }
+        );
+        assert_eq!(sub_accounts[0].spot_state.balances[0].coin, "USDC");
+
+        let state = &sub_accounts[0].clearinghouse_state;
+        let position = state.isolated_position("ETH").unwrap();
+        assert!(position.is_long().unwrap());
+        assert_eq!(position.margin().unwrap(), Decimal::new(57, 2));
+        // 11.5 / 0.57
+        assert_eq!(
+            position.current_leverage().unwrap().round_dp(2),
+            Decimal::new(2018, 2)
+        );
+        assert!(state.isolated_position("BTC").is_err());
+    }
+
+    #[test]
+    fn test_validator_summaries_deserialization() {
+        let json = r#"[{
+            "validator": "0x5ac99df645f3414876c816caa18b2d234024b487",
+            "signer": "0x6e6bd8ec9a11dbb3b4b3d8bb8b8e2a7e6e1e7d9b",
+            "name": "Hypurrscan",
+            "description": "",
+            "nRecentBlocks": 1204,
+            "stake": 4289498640735920,
+            "isJailed": false,
+            "unjailableAfter": null,
+            "isActive": true,
+            "commission": "0.04",
+            "stats": [
+                ["day", {"uptimeFraction": "1.0", "predictedApr": "0.0234", "nSamples": 1440}],
+                ["week", {"uptimeFraction": "0.999", "predictedApr": "0.0231", "nSamples": 10080}]
+            ]
+        }]"#;
+        let validators: Vec<HypeValidatorSummary> = serde_json::from_str(json).unwrap();
+        assert_eq!(validators[0].name, "Hypurrscan");
+        assert_eq!(validators[0].stats[1].0, "week");
+        assert_eq!(validators[0].stats[0].1.n_samples, 1440);
+
+        let request = serde_json::to_value(InfoRequest::ValidatorSummaries).unwrap();
+        assert_eq!(request, serde_json::json!({"type": "validatorSummaries"}));
+    }
+
+    #[test]
+    fn test_extra_agents_deserialization() {
+        let json = r#"[
+            {"name": "bot", "address": "0x0000000000000000000000000000000000000abc", "validUntil": 1800000000000},
+            {"name": "", "address": "0x0000000000000000000000000000000000000def", "validUntil": null}
+        ]"#;
+        let agents: Vec<HypeExtraAgent> = serde_json::from_str(json).unwrap();
+        assert_eq!(agents[0].name, "bot");
+        assert_eq!(agents[0].valid_until, Some(1_800_000_000_000));
+        assert!(agents[1].valid_until.is_none());
+    }
+
+    #[test]
+    fn test_active_asset_data_clamp_order_sz() {
+        use crate::actions::{Limit, Order};
+
+        let json = r#"{
+            "user": "0xb65822a30bbaaa68942d6f4c43d78704faeabbbb",
+            "coin": "BTC",
+            "leverage": {"type": "cross", "value": 20},
+            "maxTradeSzs": ["0.12345678", "0.5"],
+            "availableToTrade": ["1000.0", "4000.0"],
+            "markPx": "100000.0"
+        }"#;
+        let data: HypeActiveAssetData = serde_json::from_str(json).unwrap();
+        let mut order = OrderRequest {
+            asset: 0,
+            is_buy: true,
+            limit_px: "100000".to_string(),
+            sz: "1".to_string(),
+            reduce_only: false,
+            order_type: Order::Limit(Limit {
+                tif: "Gtc".to_string(),
+            }),
+            cloid: None,
+        };
+        assert!(data.clamp_order_sz(&mut order, 5).unwrap());
+        assert_eq!(order.sz, "0.12345");
+
+        order.is_buy = false;
+        order.sz = "0.25".to_string();
+        assert!(!data.clamp_order_sz(&mut order, 5).unwrap());
+        assert_eq!(order.sz, "0.25");
+
+        // Below the size step nothing can be traded
+        let mut data = data;
+        data.max_trade_szs[1] = "0.000009".to_string();
+        assert!(data.clamp_order_sz(&mut order, 5).is_err());
+        assert_eq!(order.sz, "0.25");
+        assert_eq!(
+            data.available_to_trade(false).unwrap(),
+            Decimal::new(4000, 0)
+        );
+    }
+
+    #[test]
+    fn test_margin_tables() {
+        let json = r#"{
+            "universe": [
+                {"name": "BTC", "szDecimals": 5, "maxLeverage": 40, "marginTableId": 56},
+                {"name": "DOGE", "szDecimals": 0, "maxLeverage": 10, "marginTableId": 10}
+            ],
+            "marginTables": [[56, {
+                "description": "tiered 40x",
+                "marginTiers": [
+                    {"lowerBound": "0.0", "maxLeverage": 40},
+                    {"lowerBound": "150000000.0", "maxLeverage": 20}
+                ]
+            }]]
+        }"#;
+        let meta: HypeMeta = serde_json::from_str(json).unwrap();
+        assert_eq!(meta.asset("DOGE").unwrap().0, 1);
+
+        let btc = meta.asset_margin_table("BTC").unwrap();
+        assert_eq!(btc.max_leverage(Decimal::new(1_000_000, 0)).unwrap(), 40);
+        assert_eq!(btc.max_leverage(Decimal::new(200_000_000, 0)).unwrap(), 20);
+
+        // 1/80 of the notional in the first tier
+        let requirement = btc
+            .margin_requirement(Decimal::new(100_000, 0), 10)
+            .unwrap();
+        assert_eq!(requirement.initial_margin, Decimal::new(10_000, 0));
+        assert_eq!(requirement.maintenance_margin, Decimal::new(1_250, 0));
+
+        // The maintenance margin is continuous across tiers:
+        // 200m / 40 - 150m * (1/40 - 1/80)
+        assert_eq!(
+            btc.maintenance_margin(Decimal::new(200_000_000, 0))
+                .unwrap()
+                .round_dp(8),
+            Decimal::new(3_125_000, 0)
+        );
+        assert!(btc
+            .margin_requirement(Decimal::new(200_000_000, 0), 40)
+            .is_err());
+
+        let doge = meta.asset_margin_table("DOGE").unwrap();
+        assert_eq!(doge.max_leverage(Decimal::new(1, 0)).unwrap(), 10);
+    }
+}
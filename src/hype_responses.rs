@@ -1,5 +1,12 @@
+use std::str::FromStr;
+
+use alloy::primitives::Address;
+use anyhow::Result;
+use rust_decimal::Decimal;
 use serde::Deserialize;
 
+use crate::hype_info::HypeLedgerDelta;
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct HypeRestingOrder {
     pub oid: u64,
@@ -13,6 +20,12 @@ pub struct HypeFilledOrder {
     pub oid: u64,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HypeTwapRunning {
+    pub twap_id: u64,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum HypeExchangeDataStatus {
@@ -22,18 +35,51 @@ pub enum HypeExchangeDataStatus {
     Error(String),
     Resting(HypeRestingOrder),
     Filled(HypeFilledOrder),
+    Running(HypeTwapRunning),
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct HypeExchangeDataStatuses {
+    #[serde(default)]
     pub statuses: Vec<HypeExchangeDataStatus>,
+    // Single status of twap orders and cancels
+    #[serde(default)]
+    pub status: Option<HypeExchangeDataStatus>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
+#[serde(try_from = "RawHypeExchangeResponse")]
 pub struct HypeExchangeResponse {
-    #[serde(rename = "type")]
     pub response_type: String,
     pub data: Option<HypeExchangeDataStatuses>,
+    // `data` of responses without statuses, e.g. the address of a created sub-account
+    pub raw_data: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct RawHypeExchangeResponse {
+    #[serde(rename = "type")]
+    response_type: String,
+    #[serde(default)]
+    data: Option<serde_json::Value>,
+}
+
+impl TryFrom<RawHypeExchangeResponse> for HypeExchangeResponse {
+    type Error = serde_json::Error;
+
+    // Object data must parse as statuses, anything else is kept as raw data
+    fn try_from(raw: RawHypeExchangeResponse) -> Result<Self, Self::Error> {
+        let (data, raw_data) = match raw.data {
+            Some(data) if data.is_object() => (Some(serde_json::from_value(data)?), None),
+            Some(data) => (None, Some(data)),
+            None => (None, None),
+        };
+        Ok(Self {
+            response_type: raw.response_type,
+            data,
+            raw_data,
+        })
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -43,3 +89,203 @@ pub enum HypeExchangeResponseStatus {
     Ok(HypeExchangeResponse),
     Err(String),
 }
+
+// Flat USDC fee expected to be deducted from withdraw3 withdrawals to Arbitrum. This is
+// the fee hyperliquid charges at the time of writing, not a value read from the exchange,
+// the fee actually charged is in the `Withdraw` ledger update of the withdrawal.
+pub const WITHDRAW3_ASSUMED_FEE_USDC: Decimal = Decimal::ONE;
+
+#[derive(Debug, Clone)]
+pub struct HypeWithdrawResult {
+    pub response: HypeExchangeResponse,
+    pub nonce: u64,
+    pub amount: Decimal,
+    // `WITHDRAW3_ASSUMED_FEE_USDC` at the time of the withdrawal, not confirmed
+    pub assumed_fee: Decimal,
+    // Fee actually charged, None until filled from the ledger with `apply_ledger_delta`
+    pub fee: Option<Decimal>,
+    // Amount arriving on Arbitrum, `amount - fee`, None until the fee is known
+    pub received: Option<Decimal>,
+}
+
+impl HypeWithdrawResult {
+    // Takes the fee from the `Withdraw` ledger delta of this withdrawal. Returns false
+    // if the delta belongs to another withdrawal.
+    pub fn apply_ledger_delta(&mut self, delta: &HypeLedgerDelta) -> Result<bool> {
+        let HypeLedgerDelta::Withdraw { nonce, fee, .. } = delta else {
+            return Ok(false);
+        };
+        if *nonce != self.nonce {
+            return Ok(false);
+        }
+        let fee = Decimal::from_str(fee)?;
+        self.fee = Some(fee);
+        self.received = Some(self.amount - fee);
+        Ok(true)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HypeVaultTransferResult {
+    pub response: HypeExchangeResponse,
+    pub vault_address: Address,
+    pub is_deposit: bool,
+    pub usd: f64,
+}
+
+// `err` status returned by hyperliquid for a signed action, as opposed to failures of
+// the wallet service or the transport. Returned through `anyhow::Error`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HypeExchangeError(pub String);
+
+impl std::fmt::Display for HypeExchangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for HypeExchangeError {}
+
+// Part of the error hyperliquid returns when withdrawing a vault deposit that is still
+// in its lock-up period
+const VAULT_LOCKUP_ERROR: &str = "lockup period";
+
+// Returned through `anyhow::Error` by vault transfers, use `downcast_ref` to match it
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HypeVaultTransferError {
+    // Withdrawal of a deposit that is still in the vault's lock-up period
+    LockedUp(String),
+}
+
+impl std::fmt::Display for HypeVaultTransferError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LockedUp(error) => write!(f, "Vault deposit is locked up: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for HypeVaultTransferError {}
+
+impl HypeVaultTransferError {
+    // Only exchange errors are mapped, wallet service and transport errors pass through
+    pub(crate) fn from_exchange_error(error: anyhow::Error) -> anyhow::Error {
+        match error.downcast_ref::<HypeExchangeError>() {
+            Some(HypeExchangeError(message))
+                if message.to_lowercase().contains(VAULT_LOCKUP_ERROR) =>
+            {
+                Self::LockedUp(message.clone()).into()
+            }
+            _ => error,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HypeIsolatedMarginResult {
+    pub response: HypeExchangeResponse,
+    pub coin: String,
+    pub previous_margin: Decimal,
+    // Margin of the position after the update, None if it could not be queried after
+    // the update succeeded
+    pub margin: Option<Decimal>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_withdraw_result_ledger_fee() {
+        let mut result = HypeWithdrawResult {
+            response: HypeExchangeResponse {
+                response_type: "default".to_string(),
+                data: None,
+                raw_data: None,
+            },
+            nonce: 7,
+            amount: Decimal::TEN,
+            assumed_fee: WITHDRAW3_ASSUMED_FEE_USDC,
+            fee: None,
+            received: None,
+        };
+        let delta = |nonce| {
+            serde_json::from_value::<HypeLedgerDelta>(serde_json::json!({
+                "type": "withdraw", "usdc": "10.0", "nonce": nonce, "fee": "0.5"
+            }))
+            .unwrap()
+        };
+        assert!(!result.apply_ledger_delta(&delta(8)).unwrap());
+        assert!(result.fee.is_none() && result.received.is_none());
+        assert!(result.apply_ledger_delta(&delta(7)).unwrap());
+        assert_eq!(result.fee, Some(Decimal::new(5, 1)));
+        assert_eq!(result.received, Some(Decimal::new(95, 1)));
+    }
+
+    #[test]
+    fn test_exchange_response_data() {
+        let json = r#"{"status": "ok", "response": {"type": "order", "data": {"statuses": [{"resting": {"oid": 1}}]}}}"#;
+        let HypeExchangeResponseStatus::Ok(response) = serde_json::from_str(json).unwrap() else {
+            panic!("expected ok response");
+        };
+        assert_eq!(response.data.unwrap().statuses.len(), 1);
+        assert!(response.raw_data.is_none());
+
+        let json = r#"{"status": "ok", "response": {"type": "createSubAccount", "data": "0x0000000000000000000000000000000000000abc"}}"#;
+        let HypeExchangeResponseStatus::Ok(response) = serde_json::from_str(json).unwrap() else {
+            panic!("expected ok response");
+        };
+        assert!(response.data.is_none());
+        assert_eq!(
+            response.raw_data.unwrap(),
+            "0x0000000000000000000000000000000000000abc"
+        );
+
+        let json = r#"{"status": "ok", "response": {"type": "twapOrder", "data": {"status": {"running": {"twapId": 77738308}}}}}"#;
+        let HypeExchangeResponseStatus::Ok(response) = serde_json::from_str(json).unwrap() else {
+            panic!("expected ok response");
+        };
+        assert!(matches!(
+            response.data.unwrap().status,
+            Some(HypeExchangeDataStatus::Running(HypeTwapRunning {
+                twap_id: 77738308
+            }))
+        ));
+
+        let json = r#"{"status": "ok", "response": {"type": "default"}}"#;
+        let HypeExchangeResponseStatus::Ok(response) = serde_json::from_str(json).unwrap() else {
+            panic!("expected ok response");
+        };
+        assert!(response.data.is_none() && response.raw_data.is_none());
+
+        // Statuses that fail to parse are an error, not raw data
+        let json = r#"{"status": "ok", "response": {"type": "order", "data": {"statuses": [{"resting": {"oid": "x"}}]}}}"#;
+        assert!(serde_json::from_str::<HypeExchangeResponseStatus>(json).is_err());
+    }
+
+    #[test]
+    fn test_vault_transfer_error_mapping() {
+        let json =
+            r#"{"status": "err", "response": "Cannot withdraw from vault during lockup period."}"#;
+        let HypeExchangeResponseStatus::Err(message) = serde_json::from_str(json).unwrap() else {
+            panic!("expected err response");
+        };
+        let error = HypeVaultTransferError::from_exchange_error(HypeExchangeError(message).into());
+        assert!(matches!(
+            error.downcast_ref::<HypeVaultTransferError>(),
+            Some(HypeVaultTransferError::LockedUp(_))
+        ));
+
+        let error = HypeVaultTransferError::from_exchange_error(
+            HypeExchangeError("Insufficient balance for vault deposit".to_string()).into(),
+        );
+        assert!(error.downcast_ref::<HypeVaultTransferError>().is_none());
+
+        // Errors that did not come from the exchange are never mapped
+        let error = HypeVaultTransferError::from_exchange_error(anyhow::anyhow!(
+            "Wallet service failed: vault is in its lockup period"
+        ));
+        assert!(error.downcast_ref::<HypeVaultTransferError>().is_none());
+    }
+}
@@ -1,7 +1,16 @@
 pub mod actions;
 pub mod client;
+pub mod hype_info;
 pub mod hype_responses;
+pub mod mids_cache;
+pub mod order_book;
 pub mod types;
+pub mod ws_buffer;
+pub mod ws_client;
+pub mod ws_messages;
 
 pub use actions::*;
 pub use client::SuperstackApiClient;
+pub use order_book::OrderBook;
+pub use ws_buffer::{WsBufferPolicy, WsLag, DEFAULT_EVENT_BUFFER};
+pub use ws_client::{WsClient, WsConfig, WsConnectionEvent, WsStream};
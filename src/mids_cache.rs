@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, Weak};
+use std::time::{Duration, Instant};
+
+use rust_decimal::Decimal;
+use tokio::time::MissedTickBehavior;
+
+use crate::client::SuperstackApiClient;
+
+#[derive(Default)]
+struct MidsCacheState {
+    mids: HashMap<String, Decimal>,
+    updated_at: Option<Instant>,
+}
+
+// Mid prices refreshed in the background. The refresh task stops once the last
+// handle to the cache is dropped.
+#[derive(Clone, Default)]
+pub struct MidsCache {
+    state: Arc<RwLock<MidsCacheState>>,
+}
+
+impl MidsCache {
+    pub(crate) fn spawn(
+        client: SuperstackApiClient,
+        dex: Option<String>,
+        refresh_interval: Duration,
+    ) -> Self {
+        let cache = Self::default();
+        let state = Arc::downgrade(&cache.state);
+        tokio::spawn(Self::refresh(client, dex, refresh_interval, state));
+        cache
+    }
+
+    async fn refresh(
+        client: SuperstackApiClient,
+        dex: Option<String>,
+        refresh_interval: Duration,
+        state: Weak<RwLock<MidsCacheState>>,
+    ) {
+        let mut interval = tokio::time::interval(refresh_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if state.strong_count() == 0 {
+                break;
+            }
+            let mids = client.all_mids(dex.as_deref()).await;
+            let Some(state) = state.upgrade() else {
+                break;
+            };
+            match mids {
+                Ok(mids) => {
+                    let mut state = state.write().unwrap();
+                    state.mids = mids;
+                    state.updated_at = Some(Instant::now());
+                }
+                Err(e) => tracing::warn!("failed to refresh mids cache: {:?}", e),
+            }
+        }
+    }
+
+    pub fn get(&self, coin: &str) -> Option<Decimal> {
+        self.state.read().unwrap().mids.get(coin).copied()
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, Decimal> {
+        self.state.read().unwrap().mids.clone()
+    }
+
+    pub fn updated_at(&self) -> Option<Instant> {
+        self.state.read().unwrap().updated_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    // Answers every info request with the mids of the current request count
+    async fn serve_mids(stream: &mut TcpStream, requests: &AtomicUsize) {
+        let mut request = Vec::new();
+        let mut buf = [0u8; 1024];
+        let body_start = loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            if let Some(i) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                break i + 4;
+            }
+        };
+        let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+        let content_length: usize = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .map(|len| len.trim().parse().unwrap())
+            .unwrap_or(0);
+        while request.len() < body_start + content_length {
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+        }
+        let body: serde_json::Value = serde_json::from_slice(&request[body_start..]).unwrap();
+        assert_eq!(body["type"], "allMids");
+
+        let count = requests.fetch_add(1, Ordering::SeqCst) + 1;
+        let mids = serde_json::json!({ "BTC": count.to_string() }).to_string();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            mids.len(),
+            mids
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_refresh_and_stop_when_dropped() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let server_requests = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let requests = server_requests.clone();
+                tokio::spawn(async move { serve_mids(&mut stream, &requests).await });
+            }
+        });
+
+        let client = SuperstackApiClient::new("")
+            .with_hyperliquid_url(&format!("http://{}", addr))
+            .with_mids_cache(None, Duration::from_millis(20));
+        let cache = client.mids_cache().unwrap().clone();
+        assert!(cache.updated_at().is_none());
+
+        // Refreshed until the mid has changed at least once
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while cache.get("BTC").is_none_or(|mid| mid < Decimal::TWO) {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+        let updated_at = cache.updated_at().unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(cache.updated_at().unwrap() > updated_at);
+        assert_eq!(cache.snapshot().len(), 1);
+
+        // The refresh task stops once the last handle is dropped
+        drop(client);
+        drop(cache);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let stopped_at = requests.load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(requests.load(Ordering::SeqCst), stopped_at);
+    }
+}
@@ -0,0 +1,402 @@
+use std::str::FromStr;
+use std::sync::{Arc, RwLock, Weak};
+
+use anyhow::Result;
+use futures_util::{FutureExt, StreamExt};
+use rust_decimal::Decimal;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::ws_client::{WsClient, WsConnectionEvent, WsStream};
+use crate::ws_messages::WsL2Book;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookLevel {
+    pub px: Decimal,
+    pub sz: Decimal,
+    pub n: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct OrderBookSnapshot {
+    pub coin: String,
+    pub time: u64,
+    // Best price first
+    pub bids: Vec<BookLevel>,
+    pub asks: Vec<BookLevel>,
+}
+
+impl TryFrom<&WsL2Book> for OrderBookSnapshot {
+    type Error = anyhow::Error;
+
+    fn try_from(book: &WsL2Book) -> Result<Self> {
+        let parse = |levels: &[crate::ws_messages::WsLevel]| {
+            levels
+                .iter()
+                .map(|level| {
+                    Ok(BookLevel {
+                        px: Decimal::from_str(&level.px)?,
+                        sz: Decimal::from_str(&level.sz)?,
+                        n: level.n,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        let bids = parse(&book.levels[0])?;
+        let asks = parse(&book.levels[1])?;
+        if !bids.windows(2).all(|w| w[0].px > w[1].px)
+            || !asks.windows(2).all(|w| w[0].px < w[1].px)
+        {
+            return Err(anyhow::anyhow!("Unsorted l2 book levels for {}", book.coin));
+        }
+
+        Ok(Self {
+            coin: book.coin.clone(),
+            time: book.time,
+            bids,
+            asks,
+        })
+    }
+}
+
+impl OrderBookSnapshot {
+    pub fn best_bid(&self) -> Option<&BookLevel> {
+        self.bids.first()
+    }
+
+    pub fn best_ask(&self) -> Option<&BookLevel> {
+        self.asks.first()
+    }
+
+    pub fn mid(&self) -> Option<Decimal> {
+        Some((self.best_bid()?.px + self.best_ask()?.px) / Decimal::TWO)
+    }
+
+    pub fn spread(&self) -> Option<Decimal> {
+        Some(self.best_ask()?.px - self.best_bid()?.px)
+    }
+
+    // Mid weighted towards the side with less size at the top of the book
+    pub fn microprice(&self) -> Option<Decimal> {
+        let bid = self.best_bid()?;
+        let ask = self.best_ask()?;
+        let total = bid.sz + ask.sz;
+        if total.is_zero() {
+            return self.mid();
+        }
+        Some((bid.px * ask.sz + ask.px * bid.sz) / total)
+    }
+
+    // Levels a taker order on this side would fill against
+    fn taker_levels(&self, is_buy: bool) -> &[BookLevel] {
+        if is_buy {
+            &self.asks
+        } else {
+            &self.bids
+        }
+    }
+
+    // Size a taker order could fill at `px` or better
+    pub fn depth_at_price(&self, is_buy: bool, px: Decimal) -> Decimal {
+        self.taker_levels(is_buy)
+            .iter()
+            .take_while(|level| {
+                if is_buy {
+                    level.px <= px
+                } else {
+                    level.px >= px
+                }
+            })
+            .map(|level| level.sz)
+            .sum()
+    }
+
+    // Size a taker order needs to fill `notional`, None if the visible book is too thin
+    pub fn size_for_notional(&self, is_buy: bool, notional: Decimal) -> Option<Decimal> {
+        let mut remaining = notional;
+        let mut sz = Decimal::ZERO;
+        for level in self.taker_levels(is_buy) {
+            let level_notional = level.px * level.sz;
+            if level_notional >= remaining {
+                return Some(sz + remaining / level.px);
+            }
+            remaining -= level_notional;
+            sz += level.sz;
+        }
+        None
+    }
+
+    // Average fill price of a taker order of size `sz`, None if the visible book is too thin
+    pub fn vwap(&self, is_buy: bool, sz: Decimal) -> Option<Decimal> {
+        if sz <= Decimal::ZERO {
+            return None;
+        }
+        let mut remaining = sz;
+        let mut notional = Decimal::ZERO;
+        for level in self.taker_levels(is_buy) {
+            let fill = remaining.min(level.sz);
+            notional += fill * level.px;
+            remaining -= fill;
+            if remaining.is_zero() {
+                return Some(notional / sz);
+            }
+        }
+        None
+    }
+}
+
+// Book of a single coin kept up to date from the l2Book stream. Each l2Book message
+// is a full snapshot of the visible levels, older or malformed messages are ignored.
+// The book is cleared when the websocket disconnects and filled again by the first
+// l2Book of the new connection. The stream is unsubscribed once the last handle to the
+// book is dropped.
+#[derive(Clone)]
+pub struct OrderBook {
+    coin: String,
+    state: Arc<RwLock<Option<Arc<OrderBookSnapshot>>>>,
+}
+
+impl OrderBook {
+    pub fn new(coin: &str) -> Self {
+        Self {
+            coin: coin.to_string(),
+            state: Arc::default(),
+        }
+    }
+
+    pub async fn subscribe(ws: &WsClient, coin: &str) -> Result<Self> {
+        let book = Self::new(coin);
+        let events = ws.connection_events();
+        let stream = ws.l2_book(coin).await?;
+        tokio::spawn(Self::update(
+            stream,
+            events,
+            Arc::downgrade(&book.state),
+            book.coin.clone(),
+        ));
+        Ok(book)
+    }
+
+    async fn update(
+        mut stream: WsStream<WsL2Book>,
+        mut events: broadcast::Receiver<WsConnectionEvent>,
+        state: Weak<RwLock<Option<Arc<OrderBookSnapshot>>>>,
+        coin: String,
+    ) {
+        loop {
+            tokio::select! {
+                biased;
+                event = events.recv(), if !events.is_closed() => {
+                    // A lagged receiver may have missed a disconnect
+                    if !matches!(
+                        event,
+                        Ok(WsConnectionEvent::Disconnected) | Err(RecvError::Lagged(_))
+                    ) {
+                        continue;
+                    }
+                    let Some(state) = state.upgrade() else {
+                        break;
+                    };
+                    *state.write().unwrap() = None;
+                    // Everything read before the disconnect is already buffered
+                    while let Some(Some(_)) = stream.next().now_or_never() {}
+                }
+                message = stream.next() => {
+                    let Some(message) = message else {
+                        break;
+                    };
+                    let Some(state) = state.upgrade() else {
+                        break;
+                    };
+                    if let Err(e) = Self::apply_to(&state, &coin, &message) {
+                        tracing::warn!("failed to apply l2 book update: {:?}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    // Returns false if the update is older than the current book
+    pub fn apply(&self, book: &WsL2Book) -> Result<bool> {
+        Self::apply_to(&self.state, &self.coin, book)
+    }
+
+    fn apply_to(
+        state: &RwLock<Option<Arc<OrderBookSnapshot>>>,
+        coin: &str,
+        book: &WsL2Book,
+    ) -> Result<bool> {
+        if book.coin != coin {
+            return Err(anyhow::anyhow!(
+                "l2 book for {} applied to the {} book",
+                book.coin,
+                coin
+            ));
+        }
+        let snapshot = OrderBookSnapshot::try_from(book)?;
+        let mut state = state.write().unwrap();
+        if state
+            .as_ref()
+            .is_some_and(|current| current.time > snapshot.time)
+        {
+            return Ok(false);
+        }
+        *state = Some(Arc::new(snapshot));
+        Ok(true)
+    }
+
+    pub fn coin(&self) -> &str {
+        &self.coin
+    }
+
+    // Consistent view of the book, None until the first update arrives and while
+    // disconnected
+    pub fn snapshot(&self) -> Option<Arc<OrderBookSnapshot>> {
+        self.state.read().unwrap().clone()
+    }
+
+    pub fn best_bid(&self) -> Option<BookLevel> {
+        self.snapshot()?.best_bid().copied()
+    }
+
+    pub fn best_ask(&self) -> Option<BookLevel> {
+        self.snapshot()?.best_ask().copied()
+    }
+
+    pub fn microprice(&self) -> Option<Decimal> {
+        self.snapshot()?.microprice()
+    }
+
+    pub fn vwap(&self, is_buy: bool, sz: Decimal) -> Option<Decimal> {
+        self.snapshot()?.vwap(is_buy, sz)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ws_client::WsConfig;
+    use futures_util::SinkExt;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::MaybeTlsStream;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn l2_book(time: u64) -> WsL2Book {
+        serde_json::from_value(l2_book_json(time)).unwrap()
+    }
+
+    fn l2_book_json(time: u64) -> serde_json::Value {
+        serde_json::json!({
+            "coin": "BTC",
+            "time": time,
+            "levels": [
+                [
+                    {"px": "100", "sz": "1", "n": 1},
+                    {"px": "99", "sz": "2", "n": 2},
+                ],
+                [
+                    {"px": "101", "sz": "3", "n": 1},
+                    {"px": "102", "sz": "1", "n": 1},
+                ],
+            ],
+        })
+    }
+
+    #[test]
+    fn test_order_book() {
+        let book = OrderBook::new("BTC");
+        assert!(book.snapshot().is_none());
+        assert!(book.apply(&l2_book(2)).unwrap());
+        assert!(!book.apply(&l2_book(1)).unwrap());
+
+        let snapshot = book.snapshot().unwrap();
+        assert_eq!(snapshot.best_bid().unwrap().px, dec("100"));
+        assert_eq!(snapshot.best_ask().unwrap().px, dec("101"));
+        assert_eq!(snapshot.mid(), Some(dec("100.5")));
+        assert_eq!(snapshot.spread(), Some(dec("1")));
+        // (100 * 3 + 101 * 1) / 4
+        assert_eq!(snapshot.microprice(), Some(dec("100.25")));
+
+        assert_eq!(snapshot.depth_at_price(true, dec("101.5")), dec("3"));
+        assert_eq!(snapshot.depth_at_price(false, dec("99")), dec("3"));
+        assert_eq!(snapshot.depth_at_price(false, dec("101")), dec("0"));
+
+        // 303 from the first ask level, 51 from the second
+        assert_eq!(
+            snapshot.size_for_notional(true, dec("354")),
+            Some(dec("3.5"))
+        );
+        assert_eq!(snapshot.size_for_notional(true, dec("1000")), None);
+
+        // (3 * 101 + 1 * 102) / 4
+        assert_eq!(snapshot.vwap(true, dec("4")), Some(dec("101.25")));
+        assert_eq!(snapshot.vwap(false, dec("0.5")), Some(dec("100")));
+        assert_eq!(snapshot.vwap(false, dec("4")), None);
+
+        let mut eth = l2_book(3);
+        eth.coin = "ETH".to_string();
+        assert!(book.apply(&eth).is_err());
+    }
+
+    async fn wait_for(book: &OrderBook, time: Option<u64>) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while book.snapshot().map(|snapshot| snapshot.time) != time {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_order_book_cleared_on_disconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (resume, resumed) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(async move {
+            let l2_book_message = |time| {
+                let message = serde_json::json!({"channel": "l2Book", "data": l2_book_json(time)});
+                Message::text(message.to_string())
+            };
+
+            // First connection sends a book and is then closed
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(MaybeTlsStream::Plain(stream))
+                .await
+                .unwrap();
+            socket.next().await.unwrap().unwrap();
+            socket.send(l2_book_message(1)).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            drop(socket);
+
+            // The new connection only sends a book once the cleared book was seen
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(MaybeTlsStream::Plain(stream))
+                .await
+                .unwrap();
+            socket.next().await.unwrap().unwrap();
+            resumed.await.unwrap();
+            socket.send(l2_book_message(2)).await.unwrap();
+            while socket.next().await.is_some() {}
+        });
+
+        let config = WsConfig {
+            min_reconnect_backoff: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let ws = WsClient::connect_with_config(&format!("ws://{}/ws", addr), config)
+            .await
+            .unwrap();
+        let book = OrderBook::subscribe(&ws, "BTC").await.unwrap();
+        wait_for(&book, Some(1)).await;
+        wait_for(&book, None).await;
+        resume.send(()).unwrap();
+        wait_for(&book, Some(2)).await;
+
+        server.abort();
+    }
+}
@@ -70,6 +70,9 @@ pub struct ExchangeRequest {
     pub action: Actions,
     pub vault_address: Option<Address>,
     pub expires_after: Option<u64>,
+    // Signs with this nonce instead of a fresh one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
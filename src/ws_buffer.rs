@@ -0,0 +1,192 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::ws_messages::{Subscription, WsMessage};
+
+// How messages are buffered for a consumer that reads slower than they arrive. The
+// socket reader never waits for consumers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WsBufferPolicy {
+    // Keeps every message, memory grows while the consumer lags
+    Unbounded,
+    // Keeps at most this many messages, dropping the oldest
+    DropOldest(usize),
+    // Keeps only the latest message, for snapshot channels such as l2Book and allMids
+    ConflateLatest,
+}
+
+// Default capacity of event channels, large enough that only a stuck consumer loses
+// messages, which is then visible in `WsLag::dropped`
+pub const DEFAULT_EVENT_BUFFER: usize = 10_000;
+
+impl WsBufferPolicy {
+    // Snapshot channels are conflated, event channels keep up to DEFAULT_EVENT_BUFFER
+    // messages so a stuck consumer cannot grow memory without bound
+    pub fn default_for(subscription: &Subscription) -> Self {
+        match subscription {
+            Subscription::AllMids { .. }
+            | Subscription::L2Book { .. }
+            | Subscription::Bbo { .. }
+            | Subscription::ActiveAssetCtx { .. }
+            | Subscription::WebData2 { .. } => Self::ConflateLatest,
+            _ => Self::DropOldest(DEFAULT_EVENT_BUFFER),
+        }
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        match self {
+            Self::Unbounded => None,
+            Self::DropOldest(capacity) => Some((*capacity).max(1)),
+            Self::ConflateLatest => Some(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WsLag {
+    // Messages dropped or replaced by a newer one before they were read
+    pub dropped: u64,
+    pub buffered: usize,
+    pub max_buffered: usize,
+}
+
+#[derive(Default)]
+struct BufferState {
+    queue: VecDeque<WsMessage>,
+    lag: WsLag,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+pub(crate) struct WsBuffer {
+    capacity: Option<usize>,
+    state: Mutex<BufferState>,
+}
+
+impl WsBuffer {
+    pub(crate) fn channel(policy: WsBufferPolicy) -> (WsBufferSender, Arc<WsBuffer>) {
+        let buffer = Arc::new(WsBuffer {
+            capacity: policy.capacity(),
+            state: Mutex::default(),
+        });
+        (WsBufferSender(buffer.clone()), buffer)
+    }
+
+    pub(crate) fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Option<WsMessage>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(message) = state.queue.pop_front() {
+            state.lag.buffered = state.queue.len();
+            return Poll::Ready(Some(message));
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    pub(crate) fn lag(&self) -> WsLag {
+        self.state.lock().unwrap().lag
+    }
+}
+
+// Write side of a buffer, dropping it ends the stream once the buffer is drained.
+pub(crate) struct WsBufferSender(Arc<WsBuffer>);
+
+impl WsBufferSender {
+    pub(crate) fn send(&self, message: WsMessage) {
+        let mut state = self.0.state.lock().unwrap();
+        if let Some(capacity) = self.0.capacity {
+            while state.queue.len() >= capacity {
+                state.queue.pop_front();
+                state.lag.dropped += 1;
+            }
+        }
+        state.queue.push_back(message);
+        state.lag.buffered = state.queue.len();
+        state.lag.max_buffered = state.lag.max_buffered.max(state.lag.buffered);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        Arc::strong_count(&self.0) == 1
+    }
+}
+
+impl Drop for WsBufferSender {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap();
+        state.closed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::task::noop_waker_ref;
+
+    fn pong_count(buffer: &WsBuffer) -> usize {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut count = 0;
+        while let Poll::Ready(Some(_)) = buffer.poll_recv(&mut cx) {
+            count += 1;
+        }
+        count
+    }
+
+    #[test]
+    fn test_buffer_policies() {
+        let (sender, buffer) = WsBuffer::channel(WsBufferPolicy::DropOldest(2));
+        for _ in 0..5 {
+            sender.send(WsMessage::Pong);
+        }
+        assert_eq!(
+            buffer.lag(),
+            WsLag {
+                dropped: 3,
+                buffered: 2,
+                max_buffered: 2
+            }
+        );
+        assert_eq!(pong_count(&buffer), 2);
+        assert_eq!(buffer.lag().buffered, 0);
+
+        let (sender, buffer) = WsBuffer::channel(WsBufferPolicy::ConflateLatest);
+        sender.send(WsMessage::Pong);
+        sender.send(WsMessage::Pong);
+        assert_eq!(buffer.lag().dropped, 1);
+        assert_eq!(pong_count(&buffer), 1);
+
+        let (sender, buffer) = WsBuffer::channel(WsBufferPolicy::Unbounded);
+        for _ in 0..100 {
+            sender.send(WsMessage::Pong);
+        }
+        assert!(!sender.is_closed());
+        drop(sender);
+        assert_eq!(buffer.lag().max_buffered, 100);
+        assert_eq!(pong_count(&buffer), 100);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        assert!(matches!(buffer.poll_recv(&mut cx), Poll::Ready(None)));
+
+        let trades = Subscription::Trades {
+            coin: "BTC".to_string(),
+        };
+        assert_eq!(
+            WsBufferPolicy::default_for(&trades),
+            WsBufferPolicy::DropOldest(DEFAULT_EVENT_BUFFER)
+        );
+        let bbo = Subscription::Bbo {
+            coin: "BTC".to_string(),
+        };
+        assert_eq!(
+            WsBufferPolicy::default_for(&bbo),
+            WsBufferPolicy::ConflateLatest
+        );
+    }
+}
@@ -0,0 +1,1115 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use alloy::primitives::Address;
+use anyhow::Result;
+use futures_util::{SinkExt, Stream, StreamExt};
+use serde_json::json;
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::time::{Instant, MissedTickBehavior};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
+
+use crate::hype_responses::{HypeExchangeError, HypeExchangeResponse, HypeExchangeResponseStatus};
+use crate::types::ExchangePayload;
+use crate::ws_buffer::{WsBuffer, WsBufferPolicy, WsBufferSender, WsLag};
+use crate::ws_messages::*;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Debug, Clone)]
+pub struct WsConfig {
+    // Hyperliquid closes connections that have not sent anything for 60 seconds
+    pub ping_interval: Duration,
+    // The connection is considered stale and reconnected after this long without any message
+    pub stale_timeout: Duration,
+    pub min_reconnect_backoff: Duration,
+    pub max_reconnect_backoff: Duration,
+    pub post_timeout: Duration,
+}
+
+impl Default for WsConfig {
+    fn default() -> Self {
+        Self {
+            ping_interval: Duration::from_secs(50),
+            stale_timeout: Duration::from_secs(90),
+            min_reconnect_backoff: Duration::from_millis(500),
+            max_reconnect_backoff: Duration::from_secs(30),
+            post_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum WsConnectionEvent {
+    Disconnected,
+    // `gaps` are the subscriptions replayed after the reconnect, messages sent while
+    // disconnected are lost and their state should be re-synced via REST. A single
+    // event so that a slow receiver cannot lag past some of them.
+    Reconnected { gaps: Vec<Subscription> },
+}
+
+enum WsCommand {
+    Subscribe {
+        subscription: Subscription,
+        sender: WsBufferSender,
+        result: oneshot::Sender<Result<u64>>,
+    },
+    Unsubscribe {
+        subscription: Subscription,
+        id: u64,
+    },
+    Post {
+        request: serde_json::Value,
+        result: oneshot::Sender<Result<WsPostResponsePayload>>,
+    },
+}
+
+// One server subscription shared by all its consumers
+struct Route {
+    subscription: Subscription,
+    consumers: Vec<(u64, WsBufferSender)>,
+}
+
+// Owns the socket and reconnects it when it is lost. Runs until every `WsClient`
+// and `WsStream` is dropped, which closes the socket.
+struct WsConnection {
+    url: String,
+    config: WsConfig,
+    socket: Socket,
+    commands: mpsc::UnboundedReceiver<WsCommand>,
+    events: broadcast::Sender<WsConnectionEvent>,
+    routes: HashMap<String, Route>,
+    next_id: u64,
+    posts: HashMap<u64, oneshot::Sender<Result<WsPostResponsePayload>>>,
+    next_post_id: u64,
+    // Posts received while reconnecting, sent once connected again
+    pending_posts: Vec<WsCommand>,
+}
+
+impl WsConnection {
+    async fn run(mut self) {
+        while self.serve().await {
+            // Posted actions may or may not have been executed, they are not retried
+            for (_, result) in self.posts.drain() {
+                let _ = result.send(Err(anyhow::anyhow!(
+                    "Websocket disconnected before the post response was received"
+                )));
+            }
+            let _ = self.events.send(WsConnectionEvent::Disconnected);
+            if !self.reconnect().await {
+                break;
+            }
+        }
+    }
+
+    // Returns false once the connection should shut down, true when it was lost.
+    async fn serve(&mut self) -> bool {
+        let mut ping = tokio::time::interval_at(
+            Instant::now() + self.config.ping_interval,
+            self.config.ping_interval,
+        );
+        ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut last_message = Instant::now();
+
+        for command in std::mem::take(&mut self.pending_posts) {
+            if let Err(e) = self.handle_command(command).await {
+                tracing::warn!("websocket send failed: {:?}", e);
+                return true;
+            }
+        }
+
+        loop {
+            tokio::select! {
+                command = self.commands.recv() => match command {
+                    Some(command) => {
+                        if let Err(e) = self.handle_command(command).await {
+                            tracing::warn!("websocket send failed: {:?}", e);
+                            return true;
+                        }
+                    }
+                    None => {
+                        let _ = self.socket.close(None).await;
+                        return false;
+                    }
+                },
+                message = self.socket.next() => {
+                    last_message = Instant::now();
+                    match message {
+                        Some(Ok(Message::Text(text))) => self.route(&text),
+                        Some(Ok(Message::Close(frame))) => {
+                            tracing::warn!("websocket closed by server: {:?}", frame);
+                            return true;
+                        }
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => {
+                            tracing::warn!("websocket error: {:?}", e);
+                            return true;
+                        }
+                        None => {
+                            tracing::warn!("websocket disconnected");
+                            return true;
+                        }
+                    }
+                },
+                _ = ping.tick() => {
+                    let request = json!({ "method": "ping" });
+                    if let Err(e) = self.socket.send(Message::text(request.to_string())).await {
+                        tracing::warn!("websocket ping failed: {:?}", e);
+                        return true;
+                    }
+                },
+                _ = tokio::time::sleep_until(last_message + self.config.stale_timeout) => {
+                    tracing::warn!(
+                        "no websocket message for {:?}, reconnecting",
+                        self.config.stale_timeout
+                    );
+                    return true;
+                },
+            }
+        }
+    }
+
+    // Reconnects with exponential backoff and replays all subscriptions. Returns false
+    // if every handle was dropped in the meantime.
+    async fn reconnect(&mut self) -> bool {
+        let mut backoff = self.config.min_reconnect_backoff;
+        loop {
+            // Subscriptions made while disconnected are replayed with the others
+            let retry = tokio::time::sleep(backoff);
+            tokio::pin!(retry);
+            loop {
+                tokio::select! {
+                    _ = &mut retry => break,
+                    command = self.commands.recv() => match command {
+                        Some(command) => self.handle_offline_command(command),
+                        None => return false,
+                    },
+                }
+            }
+            if self.commands.is_closed() {
+                return false;
+            }
+            backoff = (backoff * 2).min(self.config.max_reconnect_backoff);
+
+            tracing::info!("reconnecting websocket to {}", self.url);
+            match connect_socket(&self.url).await {
+                Ok(socket) => self.socket = socket,
+                Err(e) => {
+                    tracing::warn!("websocket reconnect failed: {:?}", e);
+                    continue;
+                }
+            }
+            if let Err(e) = self.resubscribe().await {
+                tracing::warn!("websocket resubscribe failed: {:?}", e);
+                continue;
+            }
+
+            let gaps = self
+                .routes
+                .values()
+                .map(|route| route.subscription.clone())
+                .collect();
+            let _ = self.events.send(WsConnectionEvent::Reconnected { gaps });
+            return true;
+        }
+    }
+
+    async fn resubscribe(&mut self) -> Result<()> {
+        for route in self.routes.values_mut() {
+            route.consumers.retain(|(_, sender)| !sender.is_closed());
+        }
+        self.routes.retain(|_, route| !route.consumers.is_empty());
+        for route in self.routes.values() {
+            let request = json!({ "method": "subscribe", "subscription": route.subscription });
+            self.socket.send(Message::text(request.to_string())).await?;
+        }
+        Ok(())
+    }
+
+    // Later consumers of the same subscription share the server subscription and do not
+    // receive the initial snapshot of channels like userFills. Some channels are routed
+    // without their parameters, e.g. orderUpdates has no user, so a different
+    // subscription on the same route is rejected. Returns the subscription if the server
+    // has to be subscribed to it.
+    fn add_consumer(
+        &mut self,
+        subscription: Subscription,
+        sender: WsBufferSender,
+        result: oneshot::Sender<Result<u64>>,
+    ) -> Option<Subscription> {
+        let key = subscription.routing_key();
+        if let Some(route) = self.routes.get(&key) {
+            if route.subscription != subscription {
+                let _ = result.send(Err(anyhow::anyhow!(
+                    "Already subscribed to {:?}",
+                    route.subscription
+                )));
+                return None;
+            }
+        }
+        self.next_id += 1;
+        let id = self.next_id;
+        let _ = result.send(Ok(id));
+        if let Some(route) = self.routes.get_mut(&key) {
+            route.consumers.push((id, sender));
+            return None;
+        }
+        self.routes.insert(
+            key,
+            Route {
+                subscription: subscription.clone(),
+                consumers: vec![(id, sender)],
+            },
+        );
+        Some(subscription)
+    }
+
+    // Returns the subscription if the server has to be unsubscribed from it
+    fn remove_consumer(&mut self, subscription: Subscription, id: u64) -> Option<Subscription> {
+        let key = subscription.routing_key();
+        let route = self.routes.get_mut(&key)?;
+        route.consumers.retain(|(consumer, _)| *consumer != id);
+        if !route.consumers.is_empty() {
+            return None;
+        }
+        self.routes.remove(&key);
+        Some(subscription)
+    }
+
+    // Routes are replayed by `resubscribe` and posts are sent once connected again
+    fn handle_offline_command(&mut self, command: WsCommand) {
+        match command {
+            WsCommand::Subscribe {
+                subscription,
+                sender,
+                result,
+            } => {
+                self.add_consumer(subscription, sender, result);
+            }
+            WsCommand::Unsubscribe { subscription, id } => {
+                self.remove_consumer(subscription, id);
+            }
+            WsCommand::Post { .. } => self.pending_posts.push(command),
+        }
+    }
+
+    async fn handle_command(&mut self, command: WsCommand) -> Result<()> {
+        match command {
+            WsCommand::Subscribe {
+                subscription,
+                sender,
+                result,
+            } => {
+                // The route is kept even if sending fails, it is replayed after reconnecting
+                if let Some(subscription) = self.add_consumer(subscription, sender, result) {
+                    let request = json!({ "method": "subscribe", "subscription": subscription });
+                    self.socket.send(Message::text(request.to_string())).await?;
+                }
+            }
+            WsCommand::Unsubscribe { subscription, id } => {
+                if let Some(subscription) = self.remove_consumer(subscription, id) {
+                    let request = json!({ "method": "unsubscribe", "subscription": subscription });
+                    self.socket.send(Message::text(request.to_string())).await?;
+                }
+            }
+            WsCommand::Post { request, result } => {
+                // The caller gave up waiting, e.g. while reconnecting, do not send it late
+                if result.is_closed() {
+                    return Ok(());
+                }
+                self.next_post_id += 1;
+                let id = self.next_post_id;
+                let request = json!({ "method": "post", "id": id, "request": request });
+                self.posts.insert(id, result);
+                self.socket.send(Message::text(request.to_string())).await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn route(&mut self, text: &str) {
+        let message: WsMessage = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(e) => {
+                tracing::debug!("unhandled websocket message {}: {:?}", text, e);
+                return;
+            }
+        };
+        if let WsMessage::Error(error) = &message {
+            tracing::warn!("websocket error message: {}", error);
+            return;
+        }
+        if let WsMessage::Post(post) = message {
+            match self.posts.remove(&post.id) {
+                Some(result) => {
+                    let _ = result.send(Ok(post.response));
+                }
+                None => tracing::debug!("unexpected post response {}", post.id),
+            }
+            return;
+        }
+        let Some(key) = message.routing_key() else {
+            return;
+        };
+        if let Some(route) = self.routes.get(&key) {
+            for (_, sender) in &route.consumers {
+                sender.send(message.clone());
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct WsClient {
+    commands: mpsc::UnboundedSender<WsCommand>,
+    events: broadcast::Sender<WsConnectionEvent>,
+    post_timeout: Duration,
+    buffer_policy: Option<WsBufferPolicy>,
+}
+
+impl WsClient {
+    pub async fn connect(url: &str) -> Result<Self> {
+        Self::connect_with_config(url, WsConfig::default()).await
+    }
+
+    pub async fn connect_with_config(url: &str, config: WsConfig) -> Result<Self> {
+        let socket = connect_socket(url).await?;
+        let (commands, receiver) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(64);
+        let post_timeout = config.post_timeout;
+        let connection = WsConnection {
+            url: url.to_string(),
+            config,
+            socket,
+            commands: receiver,
+            events: events.clone(),
+            routes: HashMap::new(),
+            next_id: 0,
+            posts: HashMap::new(),
+            next_post_id: 0,
+            pending_posts: Vec::new(),
+        };
+        tokio::spawn(connection.run());
+        Ok(Self {
+            commands,
+            events,
+            post_timeout,
+            buffer_policy: None,
+        })
+    }
+
+    // Streams subscribed through the returned client use this policy instead of
+    // `WsBufferPolicy::default_for`, e.g. `ws.with_buffer_policy(policy).trades("BTC")`
+    pub fn with_buffer_policy(&self, policy: WsBufferPolicy) -> Self {
+        Self {
+            buffer_policy: Some(policy),
+            ..self.clone()
+        }
+    }
+
+    pub fn connection_events(&self) -> broadcast::Receiver<WsConnectionEvent> {
+        self.events.subscribe()
+    }
+
+    // Sends a signed action over the post channel instead of the /exchange endpoint
+    pub async fn post_action(&self, payload: &ExchangePayload) -> Result<HypeExchangeResponse> {
+        let request = json!({ "type": "action", "payload": payload });
+        let response = match self.post(request).await? {
+            WsPostResponsePayload::Action(response) => response,
+            WsPostResponsePayload::Error(error) => return Err(anyhow::anyhow!(error)),
+            WsPostResponsePayload::Info(response) => {
+                return Err(anyhow::anyhow!(
+                    "Unexpected info response to action post: {}",
+                    response
+                ))
+            }
+        };
+
+        match serde_json::from_value(response)? {
+            HypeExchangeResponseStatus::Ok(response) => Ok(response),
+            HypeExchangeResponseStatus::Err(error) => Err(HypeExchangeError(error).into()),
+        }
+    }
+
+    async fn post(&self, request: serde_json::Value) -> Result<WsPostResponsePayload> {
+        let (result, response) = oneshot::channel();
+        self.commands
+            .send(WsCommand::Post { request, result })
+            .map_err(|_| anyhow::anyhow!("Websocket connection is closed"))?;
+        tokio::time::timeout(self.post_timeout, response)
+            .await
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "No post response within {:?}, the action may or may not have been executed",
+                    self.post_timeout
+                )
+            })?
+            .map_err(|_| anyhow::anyhow!("Websocket connection is closed"))?
+    }
+
+    pub async fn subscribe(&self, subscription: Subscription) -> Result<WsStream<WsMessage>> {
+        self.subscribe_with(subscription, Some).await
+    }
+
+    async fn subscribe_with<T>(
+        &self,
+        subscription: Subscription,
+        extract: fn(WsMessage) -> Option<T>,
+    ) -> Result<WsStream<T>> {
+        let policy = self
+            .buffer_policy
+            .unwrap_or_else(|| WsBufferPolicy::default_for(&subscription));
+        let (sender, buffer) = WsBuffer::channel(policy);
+        let (result, id) = oneshot::channel();
+        self.commands
+            .send(WsCommand::Subscribe {
+                subscription: subscription.clone(),
+                sender,
+                result,
+            })
+            .map_err(|_| anyhow::anyhow!("Websocket connection is closed"))?;
+        let id = id
+            .await
+            .map_err(|_| anyhow::anyhow!("Websocket connection is closed"))??;
+        Ok(WsStream {
+            buffer,
+            extract,
+            subscription,
+            id,
+            commands: self.commands.clone(),
+        })
+    }
+
+    pub async fn all_mids(&self, dex: Option<&str>) -> Result<WsStream<WsAllMids>> {
+        let subscription = Subscription::AllMids {
+            dex: dex.map(|dex| dex.to_string()),
+        };
+        self.subscribe_with(subscription, |message| match message {
+            WsMessage::AllMids(mids) => Some(mids),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn l2_book(&self, coin: &str) -> Result<WsStream<WsL2Book>> {
+        let subscription = Subscription::L2Book {
+            coin: coin.to_string(),
+            n_sig_figs: None,
+            mantissa: None,
+        };
+        self.subscribe_with(subscription, |message| match message {
+            WsMessage::L2Book(book) => Some(book),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn trades(&self, coin: &str) -> Result<WsStream<Vec<WsTrade>>> {
+        let subscription = Subscription::Trades {
+            coin: coin.to_string(),
+        };
+        self.subscribe_with(subscription, |message| match message {
+            WsMessage::Trades(trades) => Some(trades),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn candle(&self, coin: &str, interval: &str) -> Result<WsStream<WsCandle>> {
+        let subscription = Subscription::Candle {
+            coin: coin.to_string(),
+            interval: interval.to_string(),
+        };
+        self.subscribe_with(subscription, |message| match message {
+            WsMessage::Candle(candle) => Some(candle),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn bbo(&self, coin: &str) -> Result<WsStream<WsBbo>> {
+        let subscription = Subscription::Bbo {
+            coin: coin.to_string(),
+        };
+        self.subscribe_with(subscription, |message| match message {
+            WsMessage::Bbo(bbo) => Some(bbo),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn active_asset_ctx(&self, coin: &str) -> Result<WsStream<WsActiveAssetCtx>> {
+        let subscription = Subscription::ActiveAssetCtx {
+            coin: coin.to_string(),
+        };
+        self.subscribe_with(subscription, |message| match message {
+            WsMessage::ActiveAssetCtx(ctx) | WsMessage::ActiveSpotAssetCtx(ctx) => Some(ctx),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn order_updates(&self, user: Address) -> Result<WsStream<Vec<WsOrderUpdate>>> {
+        let subscription = Subscription::OrderUpdates { user };
+        self.subscribe_with(subscription, |message| match message {
+            WsMessage::OrderUpdates(updates) => Some(updates),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn user_fills(&self, user: Address) -> Result<WsStream<WsUserFills>> {
+        let subscription = Subscription::UserFills {
+            user,
+            aggregate_by_time: None,
+        };
+        self.subscribe_with(subscription, |message| match message {
+            WsMessage::UserFills(fills) => Some(fills.into()),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn user_events(&self, user: Address) -> Result<WsStream<WsUserEvent>> {
+        let subscription = Subscription::UserEvents { user };
+        self.subscribe_with(subscription, |message| match message {
+            WsMessage::User(event) => Some(event),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn user_fundings(&self, user: Address) -> Result<WsStream<WsUserFundingsData>> {
+        let subscription = Subscription::UserFundings { user };
+        self.subscribe_with(subscription, |message| match message {
+            WsMessage::UserFundings(fundings) => Some(fundings),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn user_non_funding_ledger_updates(
+        &self,
+        user: Address,
+    ) -> Result<WsStream<WsUserNonFundingLedgerUpdatesData>> {
+        let subscription = Subscription::UserNonFundingLedgerUpdates { user };
+        self.subscribe_with(subscription, |message| match message {
+            WsMessage::UserNonFundingLedgerUpdates(updates) => Some(updates),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn web_data2(&self, user: Address) -> Result<WsStream<WsWebData2>> {
+        let subscription = Subscription::WebData2 { user };
+        self.subscribe_with(subscription, |message| match message {
+            WsMessage::WebData2(data) => Some(*data),
+            _ => None,
+        })
+        .await
+    }
+}
+
+// Messages of one subscription. Dropping the stream unsubscribes once no other stream
+// of the same subscription is left.
+pub struct WsStream<T> {
+    buffer: Arc<WsBuffer>,
+    extract: fn(WsMessage) -> Option<T>,
+    subscription: Subscription,
+    id: u64,
+    commands: mpsc::UnboundedSender<WsCommand>,
+}
+
+impl<T> WsStream<T> {
+    pub fn subscription(&self) -> &Subscription {
+        &self.subscription
+    }
+
+    pub fn lag(&self) -> WsLag {
+        self.buffer.lag()
+    }
+}
+
+impl<T> Stream for WsStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        loop {
+            match self.buffer.poll_recv(cx) {
+                Poll::Ready(Some(message)) => {
+                    if let Some(item) = (self.extract)(message) {
+                        return Poll::Ready(Some(item));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<T> Drop for WsStream<T> {
+    fn drop(&mut self) {
+        let _ = self.commands.send(WsCommand::Unsubscribe {
+            subscription: self.subscription.clone(),
+            id: self.id,
+        });
+    }
+}
+
+async fn connect_socket(url: &str) -> Result<Socket> {
+    let (socket, _) =
+        tokio_tungstenite::connect_async_tls_with_config(url, None, false, Some(connector()?))
+            .await?;
+    Ok(socket)
+}
+
+fn connector() -> Result<Connector> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::aws_lc_rs::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    Ok(Connector::Rustls(Arc::new(config)))
+}
+
+// https://api.hyperliquid.xyz -> wss://api.hyperliquid.xyz/ws
+pub(crate) fn ws_url(hyperliquid_url: &str) -> String {
+    let url = if let Some(host) = hyperliquid_url.strip_prefix("https://") {
+        format!("wss://{}", host)
+    } else if let Some(host) = hyperliquid_url.strip_prefix("http://") {
+        format!("ws://{}", host)
+    } else {
+        hyperliquid_url.to_string()
+    };
+    format!("{}/ws", url.trim_end_matches('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SuperstackApiClient;
+    use tokio::net::TcpListener;
+
+    async fn next_request(socket: &mut Socket) -> serde_json::Value {
+        loop {
+            match socket.next().await.unwrap().unwrap() {
+                Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+                _ => continue,
+            }
+        }
+    }
+
+    #[test]
+    fn test_ws_url() {
+        assert_eq!(
+            ws_url("https://api.hyperliquid.xyz"),
+            "wss://api.hyperliquid.xyz/ws"
+        );
+        assert_eq!(ws_url("http://127.0.0.1:8080/"), "ws://127.0.0.1:8080/ws");
+    }
+
+    #[tokio::test]
+    async fn test_l2_book_subscription() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(MaybeTlsStream::Plain(stream))
+                .await
+                .unwrap();
+
+            let request = next_request(&mut socket).await;
+            assert_eq!(request["method"], "subscribe");
+            assert_eq!(request["subscription"]["type"], "l2Book");
+            assert_eq!(request["subscription"]["coin"], "BTC");
+
+            let messages = [
+                json!({"channel": "subscriptionResponse", "data": request}),
+                json!({"channel": "trades", "data": [{
+                    "coin": "ETH", "side": "B", "px": "1.0", "sz": "1.0",
+                    "hash": "0x00", "time": 1, "tid": 1
+                }]}),
+                json!({"channel": "l2Book", "data": {
+                    "coin": "BTC", "time": 2,
+                    "levels": [[{"px": "99.0", "sz": "1.0", "n": 1}], [{"px": "101.0", "sz": "2.0", "n": 3}]]
+                }}),
+            ];
+            for message in messages {
+                socket
+                    .send(Message::text(message.to_string()))
+                    .await
+                    .unwrap();
+            }
+
+            // The second consumer shares the subscription, dropping the first one
+            // does not unsubscribe
+            let request = next_request(&mut socket).await;
+            assert_eq!(request["method"], "subscribe");
+            assert_eq!(request["subscription"]["type"], "trades");
+
+            let request = next_request(&mut socket).await;
+            assert_eq!(request["method"], "unsubscribe");
+            assert_eq!(request["subscription"]["coin"], "BTC");
+        });
+
+        let client = SuperstackApiClient::new("").with_hyperliquid_url(&format!("http://{}", addr));
+        let ws = client.connect_ws().await.unwrap();
+        let mut book = ws.l2_book("BTC").await.unwrap();
+        let mut second = ws.l2_book("BTC").await.unwrap();
+
+        let update = book.next().await.unwrap();
+        assert_eq!(update.coin, "BTC");
+        assert_eq!(update.levels[1][0].px, "101.0");
+        assert_eq!(second.next().await.unwrap().time, 2);
+
+        drop(book);
+        let _trades = ws.trades("ETH").await.unwrap();
+        drop(second);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_all_mids_of_another_dex() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(MaybeTlsStream::Plain(stream))
+                .await
+                .unwrap();
+
+            let request = next_request(&mut socket).await;
+            assert_eq!(request["subscription"]["type"], "allMids");
+            assert_eq!(request["subscription"]["dex"], "xyz");
+            // The payload has no dex
+            let message =
+                json!({"channel": "allMids", "data": {"mids": {"xyz:XYZ100": "25000.5"}}});
+            socket
+                .send(Message::text(message.to_string()))
+                .await
+                .unwrap();
+            next_request(&mut socket).await;
+        });
+
+        let ws = WsClient::connect(&format!("ws://{}/ws", addr))
+            .await
+            .unwrap();
+        let mut mids = ws.all_mids(Some("xyz")).await.unwrap();
+        let error = ws.all_mids(None).await.err().unwrap();
+        assert!(error.to_string().starts_with("Already subscribed"));
+
+        let update = mids.next().await.unwrap();
+        assert_eq!(update.mids["xyz:XYZ100"].to_string(), "25000.5");
+        drop(mids);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_user_fills_snapshot_and_update() {
+        let user: Address = "0x0000000000000000000000000000000000000abc"
+            .parse()
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(MaybeTlsStream::Plain(stream))
+                .await
+                .unwrap();
+
+            let request = next_request(&mut socket).await;
+            assert_eq!(request["subscription"]["type"], "userFills");
+
+            let fill = json!({
+                "coin": "BTC", "px": "100000.0", "sz": "0.0001", "side": "B", "time": 1,
+                "startPosition": "0.0", "dir": "Open Long", "closedPnl": "0.0", "hash": "0x00",
+                "oid": 1, "crossed": true, "fee": "0.0045", "tid": 1, "feeToken": "USDC"
+            });
+            let messages = [
+                json!({"channel": "userFills", "data": {
+                    "isSnapshot": true, "user": "0x0000000000000000000000000000000000000ABC", "fills": [fill]
+                }}),
+                json!({"channel": "userFills", "data": {
+                    "user": "0x0000000000000000000000000000000000000abc", "fills": [fill, fill]
+                }}),
+            ];
+            for message in messages {
+                socket
+                    .send(Message::text(message.to_string()))
+                    .await
+                    .unwrap();
+            }
+            next_request(&mut socket).await;
+        });
+
+        let ws = WsClient::connect(&format!("ws://{}/ws", addr))
+            .await
+            .unwrap();
+        let mut fills = ws.user_fills(user).await.unwrap();
+        assert!(matches!(
+            fills.next().await.unwrap(),
+            WsUserFills::Snapshot(fills) if fills.len() == 1
+        ));
+        assert!(matches!(
+            fills.next().await.unwrap(),
+            WsUserFills::Update(fills) if fills.len() == 2
+        ));
+
+        drop(fills);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_order_updates_of_two_users() {
+        let first: Address = "0x0000000000000000000000000000000000000abc"
+            .parse()
+            .unwrap();
+        let second: Address = "0x0000000000000000000000000000000000000def"
+            .parse()
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (subscribed, ready) = oneshot::channel::<()>();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(MaybeTlsStream::Plain(stream))
+                .await
+                .unwrap();
+
+            // Only the first user is subscribed, the second user is rejected locally
+            let request = next_request(&mut socket).await;
+            assert_eq!(request["subscription"]["type"], "orderUpdates");
+            assert_eq!(
+                request["subscription"]["user"],
+                "0x0000000000000000000000000000000000000abc"
+            );
+            ready.await.unwrap();
+            let message = json!({"channel": "orderUpdates", "data": [{
+                "order": {
+                    "coin": "BTC", "side": "B", "limitPx": "100000.0", "sz": "0.0001",
+                    "oid": 1, "timestamp": 1, "origSz": "0.0001"
+                },
+                "status": "open",
+                "statusTimestamp": 1
+            }]});
+            socket
+                .send(Message::text(message.to_string()))
+                .await
+                .unwrap();
+
+            let request = next_request(&mut socket).await;
+            assert_eq!(request["method"], "unsubscribe");
+        });
+
+        let ws = WsClient::connect(&format!("ws://{}/ws", addr))
+            .await
+            .unwrap();
+        let mut updates = ws.order_updates(first).await.unwrap();
+        let error = ws.order_updates(second).await.err().unwrap();
+        assert!(error.to_string().starts_with("Already subscribed"));
+        let mut shared = ws.order_updates(first).await.unwrap();
+        subscribed.send(()).unwrap();
+
+        assert_eq!(updates.next().await.unwrap()[0].order.oid, 1);
+        assert_eq!(shared.next().await.unwrap()[0].order.oid, 1);
+
+        drop(updates);
+        drop(shared);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ping_and_reconnect_after_stale_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            // First connection never answers, the client pings and then gives up on it
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(MaybeTlsStream::Plain(stream))
+                .await
+                .unwrap();
+            let request = next_request(&mut socket).await;
+            assert_eq!(request["method"], "subscribe");
+            let request = next_request(&mut socket).await;
+            assert_eq!(request["method"], "ping");
+
+            // The subscription is replayed on the new connection
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(MaybeTlsStream::Plain(stream))
+                .await
+                .unwrap();
+            let request = next_request(&mut socket).await;
+            assert_eq!(request["method"], "subscribe");
+            assert_eq!(request["subscription"]["coin"], "ETH");
+            let message = json!({"channel": "bbo", "data": {
+                "coin": "ETH", "time": 1, "bbo": [{"px": "1.0", "sz": "1.0", "n": 1}, null]
+            }});
+            socket
+                .send(Message::text(message.to_string()))
+                .await
+                .unwrap();
+            next_request(&mut socket).await;
+        });
+
+        let config = WsConfig {
+            ping_interval: Duration::from_millis(100),
+            stale_timeout: Duration::from_millis(300),
+            min_reconnect_backoff: Duration::from_millis(10),
+            max_reconnect_backoff: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let ws = WsClient::connect_with_config(&format!("ws://{}/ws", addr), config)
+            .await
+            .unwrap();
+        let mut events = ws.connection_events();
+        let mut bbo = ws.bbo("ETH").await.unwrap();
+
+        let update = bbo.next().await.unwrap();
+        assert_eq!(update.coin, "ETH");
+        assert!(update.bbo[1].is_none());
+
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            WsConnectionEvent::Disconnected
+        ));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            WsConnectionEvent::Reconnected { gaps }
+                if matches!(gaps.as_slice(), [Subscription::Bbo { .. }])
+        ));
+
+        drop(bbo);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_while_reconnecting() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(MaybeTlsStream::Plain(stream))
+                .await
+                .unwrap();
+            next_request(&mut socket).await;
+            drop(socket);
+
+            // Both subscriptions are replayed on the new connection
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(MaybeTlsStream::Plain(stream))
+                .await
+                .unwrap();
+            let mut coins = Vec::new();
+            for _ in 0..2 {
+                let request = next_request(&mut socket).await;
+                assert_eq!(request["method"], "subscribe");
+                coins.push(
+                    request["subscription"]["coin"]
+                        .as_str()
+                        .unwrap()
+                        .to_string(),
+                );
+            }
+            coins.sort();
+            assert_eq!(coins, ["BTC", "ETH"]);
+        });
+
+        let config = WsConfig {
+            min_reconnect_backoff: Duration::from_millis(500),
+            ..Default::default()
+        };
+        let ws = WsClient::connect_with_config(&format!("ws://{}/ws", addr), config)
+            .await
+            .unwrap();
+        let mut events = ws.connection_events();
+        let _btc = ws.trades("BTC").await.unwrap();
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            WsConnectionEvent::Disconnected
+        ));
+
+        // Answered during the backoff instead of waiting for the reconnect
+        let _eth = tokio::time::timeout(Duration::from_millis(200), ws.trades("ETH"))
+            .await
+            .unwrap()
+            .unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_post_action() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(MaybeTlsStream::Plain(stream))
+                .await
+                .unwrap();
+
+            let first = next_request(&mut socket).await;
+            let second = next_request(&mut socket).await;
+            assert_eq!(first["method"], "post");
+            assert_eq!(first["request"]["type"], "action");
+            assert_eq!(first["request"]["payload"]["nonce"], 1);
+            assert_ne!(first["id"], second["id"]);
+
+            // Answer out of order
+            let messages = [
+                json!({"channel": "post", "data": {"id": second["id"], "response": {
+                    "type": "action",
+                    "payload": {"status": "err", "response": "Insufficient margin to place order."}
+                }}}),
+                json!({"channel": "post", "data": {"id": first["id"], "response": {
+                    "type": "action",
+                    "payload": {"status": "ok", "response": {
+                        "type": "order", "data": {"statuses": [{"resting": {"oid": 77}}]}
+                    }}
+                }}}),
+            ];
+            for message in messages {
+                socket
+                    .send(Message::text(message.to_string()))
+                    .await
+                    .unwrap();
+            }
+            let _ = socket.next().await;
+        });
+
+        let payload = |nonce| ExchangePayload {
+            action: json!({"type": "order"}),
+            nonce,
+            signature: json!({}),
+            vault_address: None,
+            expires_after: None,
+        };
+        let client = SuperstackApiClient::new("").with_hyperliquid_url(&format!("http://{}", addr));
+        let ws = client.connect_ws().await.unwrap();
+        let client = client.with_exchange_ws(ws);
+
+        let (first, second) = tokio::join!(
+            client.submit_signed(payload(1)),
+            client.submit_signed(payload(2))
+        );
+        let statuses = first.unwrap().data.unwrap().statuses;
+        assert!(matches!(
+            statuses[0],
+            crate::hype_responses::HypeExchangeDataStatus::Resting(ref order) if order.oid == 77
+        ));
+        assert!(second
+            .unwrap_err()
+            .to_string()
+            .contains("Insufficient margin"));
+
+        drop(client);
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+
+use alloy::primitives::Address;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::hype_info::{
+    HypeClearinghouseState, HypeLedgerUpdate, HypeOrderDetails, HypeOrderStatus,
+    HypeSpotClearinghouseState,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+pub enum Subscription {
+    // `allMids` messages do not carry the dex, only one dex can be subscribed to them
+    // per connection.
+    AllMids {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        dex: Option<String>,
+    },
+    // Books of the same coin with different aggregations cannot be told apart,
+    // only one of them can be subscribed at a time.
+    #[serde(rename_all = "camelCase")]
+    L2Book {
+        coin: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        n_sig_figs: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mantissa: Option<u32>,
+    },
+    Trades {
+        coin: String,
+    },
+    Candle {
+        coin: String,
+        interval: String,
+    },
+    Bbo {
+        coin: String,
+    },
+    ActiveAssetCtx {
+        coin: String,
+    },
+    // `orderUpdates` and `userEvents` messages do not carry the user, only one user
+    // can be subscribed to them per connection.
+    OrderUpdates {
+        user: Address,
+    },
+    #[serde(rename_all = "camelCase")]
+    UserFills {
+        user: Address,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        aggregate_by_time: Option<bool>,
+    },
+    UserEvents {
+        user: Address,
+    },
+    UserFundings {
+        user: Address,
+    },
+    UserNonFundingLedgerUpdates {
+        user: Address,
+    },
+    WebData2 {
+        user: Address,
+    },
+}
+
+impl Subscription {
+    // Key under which the messages of this subscription are routed
+    pub(crate) fn routing_key(&self) -> String {
+        match self {
+            Self::AllMids { .. } => "allMids".to_string(),
+            Self::L2Book { coin, .. } => format!("l2Book:{}", coin),
+            Self::Trades { coin } => format!("trades:{}", coin),
+            Self::Candle { coin, interval } => format!("candle:{}:{}", coin, interval),
+            Self::Bbo { coin } => format!("bbo:{}", coin),
+            Self::ActiveAssetCtx { coin } => format!("activeAssetCtx:{}", coin),
+            Self::OrderUpdates { .. } => "orderUpdates".to_string(),
+            Self::UserFills { user, .. } => format!("userFills:{:#x}", user),
+            Self::UserEvents { .. } => "userEvents".to_string(),
+            Self::UserFundings { user } => format!("userFundings:{:#x}", user),
+            Self::UserNonFundingLedgerUpdates { user } => {
+                format!("userNonFundingLedgerUpdates:{:#x}", user)
+            }
+            Self::WebData2 { user } => format!("webData2:{:#x}", user),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsAllMids {
+    pub mids: HashMap<String, Decimal>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WsLevel {
+    pub px: String,
+    pub sz: String,
+    pub n: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsL2Book {
+    pub coin: String,
+    pub time: u64,
+    // [bids, asks]
+    pub levels: [Vec<WsLevel>; 2],
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsTrade {
+    pub coin: String,
+    pub side: String,
+    pub px: String,
+    pub sz: String,
+    pub hash: String,
+    pub time: u64,
+    pub tid: u64,
+    // [buyer, seller]
+    #[serde(default)]
+    pub users: Option<[String; 2]>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsCandle {
+    #[serde(rename = "t")]
+    pub open_time: u64,
+    #[serde(rename = "T")]
+    pub close_time: u64,
+    #[serde(rename = "s")]
+    pub coin: String,
+    #[serde(rename = "i")]
+    pub interval: String,
+    #[serde(rename = "o")]
+    pub open: String,
+    #[serde(rename = "c")]
+    pub close: String,
+    #[serde(rename = "h")]
+    pub high: String,
+    #[serde(rename = "l")]
+    pub low: String,
+    #[serde(rename = "v")]
+    pub volume: String,
+    #[serde(rename = "n")]
+    pub num_trades: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsBbo {
+    pub coin: String,
+    pub time: u64,
+    // [best bid, best ask]
+    pub bbo: [Option<WsLevel>; 2],
+}
+
+// Perp only fields are `None` for spot assets and vice versa.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WsAssetCtx {
+    pub day_ntl_vlm: String,
+    pub prev_day_px: String,
+    pub mark_px: String,
+    #[serde(default)]
+    pub mid_px: Option<String>,
+    #[serde(default)]
+    pub funding: Option<String>,
+    #[serde(default)]
+    pub open_interest: Option<String>,
+    #[serde(default)]
+    pub oracle_px: Option<String>,
+    #[serde(default)]
+    pub premium: Option<String>,
+    #[serde(default)]
+    pub circulating_supply: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsActiveAssetCtx {
+    pub coin: String,
+    pub ctx: WsAssetCtx,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WsBasicOrder {
+    pub coin: String,
+    pub side: String,
+    pub limit_px: String,
+    pub sz: String,
+    pub oid: u64,
+    pub timestamp: u64,
+    pub orig_sz: String,
+    #[serde(default)]
+    pub cloid: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WsOrderUpdate {
+    pub order: WsBasicOrder,
+    pub status: HypeOrderStatus,
+    pub status_timestamp: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WsFill {
+    pub coin: String,
+    pub px: String,
+    pub sz: String,
+    pub side: String,
+    pub time: u64,
+    pub start_position: String,
+    pub dir: String,
+    pub closed_pnl: String,
+    pub hash: String,
+    pub oid: u64,
+    pub crossed: bool,
+    pub fee: String,
+    pub tid: u64,
+    pub fee_token: String,
+    #[serde(default)]
+    pub builder_fee: Option<String>,
+    #[serde(default)]
+    pub cloid: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WsUserFillsData {
+    #[serde(default)]
+    pub is_snapshot: Option<bool>,
+    pub user: Address,
+    pub fills: Vec<WsFill>,
+}
+
+// The first message after subscribing is a snapshot of recent fills, the following
+// ones only contain new fills.
+#[derive(Debug, Clone)]
+pub enum WsUserFills {
+    Snapshot(Vec<WsFill>),
+    Update(Vec<WsFill>),
+}
+
+impl From<WsUserFillsData> for WsUserFills {
+    fn from(data: WsUserFillsData) -> Self {
+        if data.is_snapshot.unwrap_or(false) {
+            Self::Snapshot(data.fills)
+        } else {
+            Self::Update(data.fills)
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WsFunding {
+    #[serde(default)]
+    pub time: Option<u64>,
+    pub coin: String,
+    pub usdc: String,
+    pub szi: String,
+    pub funding_rate: String,
+    #[serde(default)]
+    pub n_samples: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WsUserFundingsData {
+    #[serde(default)]
+    pub is_snapshot: Option<bool>,
+    pub user: Address,
+    pub fundings: Vec<WsFunding>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WsUserNonFundingLedgerUpdatesData {
+    #[serde(default)]
+    pub is_snapshot: Option<bool>,
+    pub user: Address,
+    pub non_funding_ledger_updates: Vec<HypeLedgerUpdate>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsLiquidation {
+    pub lid: u64,
+    pub liquidator: String,
+    pub liquidated_user: String,
+    pub liquidated_ntl_pos: String,
+    pub liquidated_account_value: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsNonUserCancel {
+    pub coin: String,
+    pub oid: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum WsUserEvent {
+    Fills(Vec<WsFill>),
+    Funding(WsFunding),
+    Liquidation(WsLiquidation),
+    NonUserCancel(Vec<WsNonUserCancel>),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WsWebData2 {
+    pub user: Address,
+    pub clearinghouse_state: HypeClearinghouseState,
+    #[serde(default)]
+    pub open_orders: Vec<HypeOrderDetails>,
+    #[serde(default)]
+    pub spot_state: Option<HypeSpotClearinghouseState>,
+    #[serde(default)]
+    pub agent_address: Option<String>,
+    #[serde(default)]
+    pub agent_valid_until: Option<u64>,
+    #[serde(default)]
+    pub is_vault: bool,
+    pub server_time: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", content = "payload")]
+#[serde(rename_all = "camelCase")]
+pub enum WsPostResponsePayload {
+    Action(serde_json::Value),
+    Info(serde_json::Value),
+    Error(String),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsPostResponse {
+    pub id: u64,
+    pub response: WsPostResponsePayload,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "channel", content = "data")]
+#[serde(rename_all = "camelCase")]
+pub enum WsMessage {
+    AllMids(WsAllMids),
+    L2Book(WsL2Book),
+    Trades(Vec<WsTrade>),
+    Candle(WsCandle),
+    Bbo(WsBbo),
+    ActiveAssetCtx(WsActiveAssetCtx),
+    ActiveSpotAssetCtx(WsActiveAssetCtx),
+    OrderUpdates(Vec<WsOrderUpdate>),
+    User(WsUserEvent),
+    UserFills(WsUserFillsData),
+    UserFundings(WsUserFundingsData),
+    UserNonFundingLedgerUpdates(WsUserNonFundingLedgerUpdatesData),
+    WebData2(Box<WsWebData2>),
+    Post(WsPostResponse),
+    SubscriptionResponse(serde_json::Value),
+    Pong,
+    Error(String),
+}
+
+impl WsMessage {
+    // Key of the subscription this message belongs to, see `Subscription::routing_key`
+    pub(crate) fn routing_key(&self) -> Option<String> {
+        match self {
+            Self::AllMids(_) => Some("allMids".to_string()),
+            Self::L2Book(book) => Some(format!("l2Book:{}", book.coin)),
+            Self::Trades(trades) => trades.first().map(|trade| format!("trades:{}", trade.coin)),
+            Self::Candle(candle) => Some(format!("candle:{}:{}", candle.coin, candle.interval)),
+            Self::Bbo(bbo) => Some(format!("bbo:{}", bbo.coin)),
+            Self::ActiveAssetCtx(ctx) | Self::ActiveSpotAssetCtx(ctx) => {
+                Some(format!("activeAssetCtx:{}", ctx.coin))
+            }
+            Self::OrderUpdates(_) => Some("orderUpdates".to_string()),
+            Self::User(_) => Some("userEvents".to_string()),
+            Self::UserFills(fills) => Some(format!("userFills:{:#x}", fills.user)),
+            Self::UserFundings(fundings) => Some(format!("userFundings:{:#x}", fundings.user)),
+            Self::UserNonFundingLedgerUpdates(updates) => {
+                Some(format!("userNonFundingLedgerUpdates:{:#x}", updates.user))
+            }
+            Self::WebData2(data) => Some(format!("webData2:{:#x}", data.user)),
+            Self::Post(_) | Self::SubscriptionResponse(_) | Self::Pong | Self::Error(_) => None,
+        }
+    }
+}